@@ -99,7 +99,13 @@ impl FlycheckHandle {
 
     /// Schedule a re-start of the cargo check worker.
     pub fn restart(&self) {
-        self.sender.send(StateChange::Restart).unwrap();
+        self.sender.send(StateChange::Restart { package: None }).unwrap();
+    }
+
+    /// Schedule a re-start of the cargo check worker, checking only the given
+    /// package instead of the whole workspace.
+    pub fn restart_for_package(&self, package: String) {
+        self.sender.send(StateChange::Restart { package: Some(package) }).unwrap();
     }
 
     /// Stop this cargo check worker.
@@ -150,7 +156,7 @@ pub enum Progress {
 }
 
 enum StateChange {
-    Restart,
+    Restart { package: Option<String> },
     Cancel,
 }
 
@@ -169,6 +175,8 @@ struct FlycheckActor {
     /// have to wrap sub-processes output handling in a thread and pass messages
     /// back over a channel.
     cargo_handle: Option<CargoHandle>,
+    /// The package the current check is restricted to, if any.
+    package: Option<String>,
 }
 
 enum Event {
@@ -184,7 +192,14 @@ impl FlycheckActor {
         workspace_root: AbsPathBuf,
     ) -> FlycheckActor {
         tracing::info!(%id, ?workspace_root, "Spawning flycheck");
-        FlycheckActor { id, sender, config, root: workspace_root, cargo_handle: None }
+        FlycheckActor {
+            id,
+            sender,
+            config,
+            root: workspace_root,
+            cargo_handle: None,
+            package: None,
+        }
     }
 
     fn report_progress(&self, progress: Progress) {
@@ -210,15 +225,22 @@ impl FlycheckActor {
                     tracing::debug!(flycheck_id = self.id, "flycheck cancelled");
                     self.cancel_check_process();
                 }
-                Event::RequestStateChange(StateChange::Restart) => {
+                Event::RequestStateChange(StateChange::Restart { mut package }) => {
                     // Cancel the previously spawned process
                     self.cancel_check_process();
                     while let Ok(restart) = inbox.recv_timeout(Duration::from_millis(50)) {
-                        // restart chained with a stop, so just cancel
-                        if let StateChange::Cancel = restart {
-                            continue 'event;
+                        match restart {
+                            // restart chained with a stop, so just cancel
+                            StateChange::Cancel => continue 'event,
+                            // restarts for different packages, check the whole workspace
+                            StateChange::Restart { package: other } => {
+                                if other != package {
+                                    package = None;
+                                }
+                            }
                         }
                     }
+                    self.package = package;
 
                     let command = self.check_command();
                     tracing::debug!(?command, "will restart flycheck");
@@ -307,10 +329,18 @@ impl FlycheckActor {
                 extra_env,
                 ansi_color_output,
             } => {
-                let mut cmd = Command::new(toolchain::cargo());
+                let mut cmd = Command::new(toolchain::cargo_with_env(extra_env));
                 cmd.arg(command);
                 cmd.current_dir(&self.root);
-                cmd.arg("--workspace");
+                match &self.package {
+                    Some(package) => {
+                        cmd.arg("-p");
+                        cmd.arg(package);
+                    }
+                    None => {
+                        cmd.arg("--workspace");
+                    }
+                }
 
                 cmd.arg(if *ansi_color_output {
                     "--message-format=json-diagnostic-rendered-ansi"
@@ -524,3 +554,43 @@ enum JsonMessage {
     Cargo(cargo_metadata::Message),
     Rustc(Diagnostic),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_args(package: Option<&str>) -> Vec<String> {
+        let config = FlycheckConfig::CargoCommand {
+            command: "check".to_owned(),
+            target_triples: Vec::new(),
+            all_targets: false,
+            no_default_features: false,
+            all_features: false,
+            features: Vec::new(),
+            extra_args: Vec::new(),
+            extra_env: FxHashMap::default(),
+            ansi_color_output: false,
+        };
+        let root = if cfg!(windows) { "C:\\ws" } else { "/ws" };
+        let mut actor =
+            FlycheckActor::new(0, Box::new(|_| ()), config, AbsPathBuf::assert(root.into()));
+        actor.package = package.map(ToOwned::to_owned);
+        let cmd = actor.check_command();
+        cmd.get_args().map(|it| it.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn restart_for_package_checks_package() {
+        let args = check_args(Some("member-a"));
+        assert_eq!(args[..3], ["check", "-p", "member-a"]);
+        assert!(!args.iter().any(|it| it == "--workspace"), "{args:?}");
+    }
+
+    #[test]
+    fn restart_checks_workspace() {
+        // This is what `check.workspace = true`, the default, does on save.
+        let args = check_args(None);
+        assert_eq!(args[..2], ["check", "--workspace"]);
+        assert!(!args.iter().any(|it| it == "-p"), "{args:?}");
+    }
+}
@@ -1,7 +1,9 @@
 use syntax::{
+    algo::non_trivia_sibling,
     ast::{self, AstNode, HasAttrs},
+    Direction,
     SyntaxKind::{COMMENT, WHITESPACE},
-    TextSize,
+    TextSize, T,
 };
 
 use crate::{AssistContext, AssistId, AssistKind, Assists};
@@ -25,34 +27,47 @@ use crate::{AssistContext, AssistId, AssistKind, Assists};
 // }
 // ```
 pub(crate) fn generate_derive(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
-    let cap = ctx.config.snippet_cap?;
+    let cap = ctx.config.snippet_cap;
     let nominal = ctx.find_node_at_offset::<ast::Adt>()?;
     let node_start = derive_insertion_offset(&nominal)?;
     let target = nominal.syntax().text_range();
+    let derive_attr = nominal
+        .attrs()
+        .filter_map(|x| x.as_simple_call())
+        .filter(|(name, _arg)| name == "derive")
+        .map(|(_name, arg)| arg)
+        .next();
+    let existing = match derive_attr {
+        Some(tt) => {
+            let r_paren = tt.right_delimiter_token()?;
+            let last = non_trivia_sibling(r_paren.clone().into(), Direction::Prev);
+            let ws_before_r_paren =
+                r_paren.prev_token().map_or(false, |it| it.kind() == WHITESPACE);
+            let separator = match last.map(|it| it.kind()) {
+                Some(T!['(']) => "",
+                Some(T![,]) if ws_before_r_paren => "",
+                Some(T![,]) => " ",
+                _ => ", ",
+            };
+            // Without snippets there is no cursor to place, so the assist would do nothing.
+            if cap.is_none() && separator.is_empty() {
+                return None;
+            }
+            Some((r_paren.text_range().start(), separator))
+        }
+        None => None,
+    };
     acc.add(
         AssistId("generate_derive", AssistKind::Generate),
         "Add `#[derive]`",
         target,
-        |builder| {
-            let derive_attr = nominal
-                .attrs()
-                .filter_map(|x| x.as_simple_call())
-                .filter(|(name, _arg)| name == "derive")
-                .map(|(_name, arg)| arg)
-                .next();
-            match derive_attr {
-                None => {
-                    builder.insert_snippet(cap, node_start, "#[derive($0)]\n");
-                }
-                Some(tt) => {
-                    // Just move the cursor.
-                    builder.insert_snippet(
-                        cap,
-                        tt.syntax().text_range().end() - TextSize::of(')'),
-                        "$0",
-                    )
-                }
-            };
+        |builder| match (existing, cap) {
+            (None, Some(cap)) => builder.insert_snippet(cap, node_start, "#[derive($0)]\n"),
+            (None, None) => builder.insert(node_start, "#[derive()]\n"),
+            (Some((offset, separator)), Some(cap)) => {
+                builder.insert_snippet(cap, offset, format!("{separator}$0"))
+            }
+            (Some((offset, separator)), None) => builder.insert(offset, separator),
         },
     )
 }
@@ -68,7 +83,10 @@ fn derive_insertion_offset(nominal: &ast::Adt) -> Option<TextSize> {
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::{check_assist, check_assist_target};
+    use crate::tests::{
+        check_assist, check_assist_no_snippet_cap, check_assist_not_applicable_no_snippet_cap,
+        check_assist_target,
+    };
 
     use super::*;
 
@@ -91,7 +109,54 @@ mod tests {
         check_assist(
             generate_derive,
             "#[derive(Clone)]\nstruct Foo { a: i32$0, }",
-            "#[derive(Clone$0)]\nstruct Foo { a: i32, }",
+            "#[derive(Clone, $0)]\nstruct Foo { a: i32, }",
+        );
+        check_assist(
+            generate_derive,
+            "#[derive(Clone,)]\nstruct Foo { a: i32$0, }",
+            "#[derive(Clone, $0)]\nstruct Foo { a: i32, }",
+        );
+        check_assist(
+            generate_derive,
+            "#[derive(Clone, )]\nstruct Foo { a: i32$0, }",
+            "#[derive(Clone, $0)]\nstruct Foo { a: i32, }",
+        );
+        check_assist(
+            generate_derive,
+            "#[derive()]\nstruct Foo { a: i32$0, }",
+            "#[derive($0)]\nstruct Foo { a: i32, }",
+        );
+    }
+
+    #[test]
+    fn add_derive_keeps_attribute_order() {
+        check_assist(
+            generate_derive,
+            "/// Docs.\n#[repr(C)]\n#[derive(Clone)]\n#[allow(dead_code)]\nenum Foo { A$0 }",
+            "/// Docs.\n#[repr(C)]\n#[derive(Clone, $0)]\n#[allow(dead_code)]\nenum Foo { A }",
+        );
+    }
+
+    #[test]
+    fn add_derive_without_snippets() {
+        check_assist_no_snippet_cap(
+            generate_derive,
+            "struct Foo { a: i32$0, }",
+            "#[derive()]\nstruct Foo { a: i32, }",
+        );
+        check_assist_no_snippet_cap(
+            generate_derive,
+            "#[derive(Clone)]\nstruct Foo { a: i32$0, }",
+            "#[derive(Clone, )]\nstruct Foo { a: i32, }",
+        );
+        check_assist_no_snippet_cap(
+            generate_derive,
+            "#[derive(Clone,)]\nstruct Foo { a: i32$0, }",
+            "#[derive(Clone, )]\nstruct Foo { a: i32, }",
+        );
+        check_assist_not_applicable_no_snippet_cap(
+            generate_derive,
+            "#[derive()]\nstruct Foo { a: i32$0, }",
         );
     }
 
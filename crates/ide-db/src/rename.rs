@@ -182,6 +182,16 @@ fn rename_mod(
         return Ok(source_change);
     }
 
+    if let Some(parent) = module.parent(sema.db) {
+        let new_name = new_name.trim_start_matches("r#");
+        let collides = parent.children(sema.db).any(|child| {
+            child != module && child.name(sema.db).map_or(false, |it| it.to_smol_str() == new_name)
+        });
+        if collides {
+            bail!("Cannot rename module to `{new_name}`, a module with that name already exists");
+        }
+    }
+
     let InFile { file_id, value: def_source } = module.definition_source(sema.db);
     if let ModuleSource::SourceFile(..) = def_source {
         let new_name = new_name.trim_start_matches("r#");
@@ -796,6 +796,16 @@ impl ReferenceCategory {
                     }
                     Some(ReferenceCategory::Read)
                 },
+                ast::RefExpr(expr) => {
+                    // A `&mut` borrow of the variable or field itself is a Write as well.
+                    let inner = expr.expr()?;
+                    if expr.mut_token().is_some()
+                        && inner.syntax().text_range().end() == r.syntax().text_range().end()
+                    {
+                        return Some(ReferenceCategory::Write);
+                    }
+                    None
+                },
                 _ => None
             }
         }
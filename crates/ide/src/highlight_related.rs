@@ -477,6 +477,27 @@ fn foo() {
         );
     }
 
+    #[test]
+    fn test_hl_local_mut_borrow_and_shadowing() {
+        check(
+            r#"
+fn foo() {
+    let mut bar = 3;
+         // ^^^ write
+    let baz = &mut bar$0;
+                // ^^^ write
+    bar = 4;
+ // ^^^ write
+    let qux = &bar;
+            // ^^^ read
+    let bar = bar + 1;
+           // ^^^ read
+    bar;
+}
+"#,
+        );
+    }
+
     #[test]
     fn test_hl_local_in_attr() {
         check(
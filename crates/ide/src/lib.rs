@@ -101,6 +101,7 @@ pub use crate::{
         tags::{Highlight, HlMod, HlMods, HlOperator, HlPunct, HlTag},
         HighlightConfig, HlRange,
     },
+    typing::TypingConfig,
 };
 pub use hir::{Documentation, Semantics};
 pub use ide_assists::{
@@ -354,17 +355,14 @@ impl Analysis {
         &self,
         position: FilePosition,
         char_typed: char,
-        autoclose: bool,
+        config: &TypingConfig,
     ) -> Cancellable<Option<SourceChange>> {
         // Fast path to not even parse the file.
         if !typing::TRIGGER_CHARS.contains(char_typed) {
             return Ok(None);
         }
-        if char_typed == '<' && !autoclose {
-            return Ok(None);
-        }
 
-        self.with_db(|db| typing::on_char_typed(db, position, char_typed))
+        self.with_db(|db| typing::on_char_typed(db, position, char_typed, config))
     }
 
     /// Returns a tree representation of symbols in the file. Useful to draw a
@@ -576,6 +576,33 @@ impl Foo {
         );
     }
 
+    #[test]
+    fn test_rename_mod_collision() {
+        check(
+            "bar",
+            r#"
+mod foo$0 {}
+mod bar {}
+"#,
+            "error: Cannot rename module to `bar`, a module with that name already exists",
+        );
+    }
+
+    #[test]
+    fn test_will_rename_file_collision() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /main.rs
+mod foo;
+mod bar;
+//- /foo.rs
+$0
+//- /bar.rs
+"#,
+        );
+        assert!(analysis.will_rename_file(position.file_id, "bar").unwrap().is_none());
+    }
+
     #[test]
     fn test_rename_for_local() {
         check(
@@ -8,6 +8,7 @@ mod format;
 mod macro_;
 mod inject;
 mod escape;
+mod comment;
 
 mod html;
 #[cfg(test)]
@@ -21,8 +22,9 @@ use syntax::{
 
 use crate::{
     syntax_highlighting::{
-        escape::highlight_escape_string, format::highlight_format_string, highlights::Highlights,
-        macro_::MacroHighlighter, tags::Highlight,
+        comment::highlight_comment, escape::highlight_escape_string,
+        format::highlight_format_string, highlights::Highlights, macro_::MacroHighlighter,
+        tags::Highlight,
     },
     FileId, HlMod, HlOperator, HlPunct, HlTag,
 };
@@ -94,6 +96,7 @@ pub struct HighlightConfig {
 // number:: Emitted for numeric literals.
 // string:: Emitted for string literals.
 // escapeSequence:: Emitted for escaped sequences inside strings like `\n`.
+// invalidEscapeSequence:: Emitted for malformed escape sequences inside strings like `\q`.
 // formatSpecifier:: Emitted for format specifiers `{:?}` in `format!`-like macros.
 //
 // - For operators:
@@ -125,6 +128,7 @@ pub struct HighlightConfig {
 // [horizontal]
 // builtinAttribute:: Emitted for names to builtin attributes in attribute path, the `repr` in `#[repr(u8)]` for example.
 // builtinType:: Emitted for builtin types like `u32`, `str` and `f32`.
+// codeSpan:: Emitted for code spans in backticks inside of comments.
 // comment:: Emitted for comments.
 // constParameter:: Emitted for const parameters.
 // deriveHelper:: Emitted for derive helper attributes.
@@ -137,6 +141,7 @@ pub struct HighlightConfig {
 // property:: Emitted for struct and union fields.
 // selfKeyword:: Emitted for the self function parameter and self path-specifier.
 // selfTypeKeyword:: Emitted for the Self type parameter.
+// taskMarker:: Emitted for task markers like `FIXME` in comments.
 // toolModule:: Emitted for tool modules.
 // typeParameter:: Emitted for type parameters.
 // unresolvedReference:: Emitted for unresolved references, names that rust-analyzer can't find the definition of.
@@ -430,6 +435,10 @@ fn traverse(
             }
         }
 
+        if let Some(comment) = descended_element.as_token().cloned().and_then(ast::Comment::cast) {
+            highlight_comment(hl, &comment, range.start());
+        }
+
         let element = match descended_element {
             NodeOrToken::Node(name_like) => highlight::name_like(
                 sema,
@@ -0,0 +1,81 @@
+//! Syntax highlighting for task markers and code spans in comments
+use crate::syntax_highlighting::highlights::Highlights;
+use crate::{HlRange, HlTag};
+use syntax::{ast, AstToken, TextRange, TextSize};
+
+const MARKERS: &[&str] = &["TODO", "FIXME"];
+
+pub(super) fn highlight_comment(stack: &mut Highlights, comment: &ast::Comment, start: TextSize) {
+    if comment.kind().doc.is_some() {
+        // Doc comments get their own highlighting injection.
+        return;
+    }
+    let text = comment.text();
+    let code_spans = code_spans(text);
+    for &range in &code_spans {
+        stack.add(HlRange {
+            range: range + start,
+            highlight: HlTag::CodeSpan.into(),
+            binding_hash: None,
+        });
+    }
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    for marker in MARKERS {
+        for (idx, _) in text.match_indices(marker) {
+            let end = idx + marker.len();
+            let bounded =
+                !text[..idx].ends_with(is_word_char) && !text[end..].starts_with(is_word_char);
+            if !bounded {
+                continue;
+            }
+            let range =
+                TextRange::new(TextSize::try_from(idx).unwrap(), TextSize::try_from(end).unwrap());
+            // A marker in a code span is just code.
+            if code_spans.iter().any(|span| span.contains_range(range)) {
+                continue;
+            }
+            stack.add(HlRange {
+                range: range + start,
+                highlight: HlTag::TaskMarker.into(),
+                binding_hash: None,
+            });
+        }
+    }
+}
+
+/// Finds the code spans in `text`, backticks included. Like in markdown, a span
+/// opened by a run of backticks is closed by the next run of the same length.
+fn code_spans(text: &str) -> Vec<TextRange> {
+    let backtick_run = |from: usize| {
+        let len = text[from..].find(|c| c != '`').unwrap_or(text.len() - from);
+        (from, len)
+    };
+
+    let mut res = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = text[pos..].find('`') {
+        let (open, len) = backtick_run(pos + offset);
+        pos = open + len;
+        let mut close = None;
+        let mut search = pos;
+        while let Some(offset) = text[search..].find('`') {
+            let (idx, run) = backtick_run(search + offset);
+            if run == len {
+                close = Some(idx);
+                break;
+            }
+            search = idx + run;
+        }
+        // An unclosed run is just literal backticks.
+        if let Some(close) = close {
+            let end = close + len;
+            res.push(TextRange::new(
+                TextSize::try_from(open).unwrap(),
+                TextSize::try_from(end).unwrap(),
+            ));
+            pos = end;
+        }
+    }
+    res
+}
@@ -11,6 +11,12 @@ pub(super) fn highlight_escape_string<T: IsString>(
 ) {
     string.escaped_char_ranges(&mut |piece_range, char| {
         if char.is_err() {
+            // Reported as a syntax error as well.
+            stack.add(HlRange {
+                range: piece_range + start,
+                highlight: HlTag::InvalidEscapeSequence.into(),
+                binding_hash: None,
+            });
             return;
         }
 
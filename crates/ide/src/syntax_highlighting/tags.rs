@@ -26,14 +26,17 @@ pub enum HlTag {
     BuiltinType,
     ByteLiteral,
     CharLiteral,
+    CodeSpan,
     Comment,
     EscapeSequence,
     FormatSpecifier,
+    InvalidEscapeSequence,
     Keyword,
     NumericLiteral,
     Operator(HlOperator),
     Punctuation(HlPunct),
     StringLiteral,
+    TaskMarker,
     UnresolvedReference,
 
     // For things which don't have a specific highlight.
@@ -162,9 +165,11 @@ impl HlTag {
             HlTag::BuiltinType => "builtin_type",
             HlTag::ByteLiteral => "byte_literal",
             HlTag::CharLiteral => "char_literal",
+            HlTag::CodeSpan => "code_span",
             HlTag::Comment => "comment",
             HlTag::EscapeSequence => "escape_sequence",
             HlTag::FormatSpecifier => "format_specifier",
+            HlTag::InvalidEscapeSequence => "invalid_escape_sequence",
             HlTag::Keyword => "keyword",
             HlTag::Punctuation(punct) => match punct {
                 HlPunct::Bracket => "bracket",
@@ -187,6 +192,7 @@ impl HlTag {
                 HlOperator::Other => "operator",
             },
             HlTag::StringLiteral => "string_literal",
+            HlTag::TaskMarker => "task_marker",
             HlTag::UnresolvedReference => "unresolved_reference",
             HlTag::None => "none",
         }
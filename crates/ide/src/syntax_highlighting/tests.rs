@@ -1016,6 +1016,45 @@ struct Foo {
     assert_eq!(&highlights[0].highlight.to_string(), "field.declaration.public");
 }
 
+#[test]
+fn test_task_markers() {
+    let (analysis, file_id) = fixture::file(
+        r#"
+// FIXME: fix
+/* ok, FIXMEs FIXME */
+/// FIXME
+fn f() {}
+"#
+        .trim(),
+    );
+
+    let markers: Vec<_> = analysis
+        .highlight(HL_CONFIG, file_id)
+        .unwrap()
+        .into_iter()
+        .filter(|it| it.highlight.tag == HlTag::TaskMarker)
+        .map(|it| it.range)
+        .collect();
+    assert_eq!(markers, [TextRange::at(3.into(), 5.into()), TextRange::at(28.into(), 5.into())]);
+}
+
+#[test]
+fn test_comments() {
+    check_highlighting(
+        r#"
+// FIXME: call `Foo::new` once it exists
+/* ``a `b` c`` and `FIXME`, but not `unclosed */
+/// `doc` comments are injected instead
+fn main() {
+    let _ = "\q\n";
+    let _ = "\x80\u{d800}";
+}
+"#,
+        expect_file!["./test_data/highlight_comments.html"],
+        false,
+    );
+}
+
 #[test]
 fn ranges_sorted() {
     let (analysis, file_id) = fixture::file(
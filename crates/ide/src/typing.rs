@@ -34,6 +34,12 @@ pub(crate) use on_enter::on_enter;
 // Don't forget to add new trigger characters to `server_capabilities` in `caps.rs`.
 pub(crate) const TRIGGER_CHARS: &str = ".=<>{";
 
+pub struct TypingConfig {
+    pub autoclose_angle: bool,
+    pub chain_indent: bool,
+    pub semicolon_on_eq: bool,
+}
+
 struct ExtendedTextEdit {
     edit: TextEdit,
     is_snippet: bool,
@@ -64,6 +70,7 @@ pub(crate) fn on_char_typed(
     db: &RootDatabase,
     position: FilePosition,
     char_typed: char,
+    config: &TypingConfig,
 ) -> Option<SourceChange> {
     if !stdx::always!(TRIGGER_CHARS.contains(char_typed)) {
         return None;
@@ -72,7 +79,7 @@ pub(crate) fn on_char_typed(
     if !stdx::always!(file.tree().syntax().text().char_at(position.offset) == Some(char_typed)) {
         return None;
     }
-    let edit = on_char_typed_inner(file, position.offset, char_typed)?;
+    let edit = on_char_typed_inner(file, position.offset, char_typed, config)?;
     let mut sc = SourceChange::from_text_edit(position.file_id, edit.edit);
     sc.is_snippet = edit.is_snippet;
     Some(sc)
@@ -82,14 +89,15 @@ fn on_char_typed_inner(
     file: &Parse<SourceFile>,
     offset: TextSize,
     char_typed: char,
+    config: &TypingConfig,
 ) -> Option<ExtendedTextEdit> {
     if !stdx::always!(TRIGGER_CHARS.contains(char_typed)) {
         return None;
     }
     return match char_typed {
-        '.' => conv(on_dot_typed(&file.tree(), offset)),
-        '=' => conv(on_eq_typed(&file.tree(), offset)),
-        '<' => on_left_angle_typed(&file.tree(), offset),
+        '.' if config.chain_indent => conv(on_dot_typed(&file.tree(), offset)),
+        '=' if config.semicolon_on_eq => conv(on_eq_typed(&file.tree(), offset)),
+        '<' if config.autoclose_angle => on_left_angle_typed(&file.tree(), offset),
         '>' => conv(on_right_angle_typed(&file.tree(), offset)),
         '{' => conv(on_opening_brace_typed(file, offset)),
         _ => return None,
@@ -386,26 +394,37 @@ mod tests {
         }
     }
 
-    fn do_type_char(char_typed: char, before: &str) -> Option<String> {
+    const TEST_CONFIG: TypingConfig =
+        TypingConfig { autoclose_angle: true, chain_indent: true, semicolon_on_eq: true };
+
+    fn do_type_char(char_typed: char, before: &str, config: &TypingConfig) -> Option<String> {
         let (offset, mut before) = extract_offset(before);
         let edit = TextEdit::insert(offset, char_typed.to_string());
         edit.apply(&mut before);
         let parse = SourceFile::parse(&before);
-        on_char_typed_inner(&parse, offset, char_typed).map(|it| {
+        on_char_typed_inner(&parse, offset, char_typed, config).map(|it| {
             it.apply(&mut before);
             before.to_string()
         })
     }
 
     fn type_char(char_typed: char, ra_fixture_before: &str, ra_fixture_after: &str) {
-        let actual = do_type_char(char_typed, ra_fixture_before)
+        let actual = do_type_char(char_typed, ra_fixture_before, &TEST_CONFIG)
             .unwrap_or_else(|| panic!("typing `{char_typed}` did nothing"));
 
         assert_eq_text!(ra_fixture_after, &actual);
     }
 
     fn type_char_noop(char_typed: char, ra_fixture_before: &str) {
-        let file_change = do_type_char(char_typed, ra_fixture_before);
+        type_char_noop_with_config(char_typed, ra_fixture_before, &TEST_CONFIG)
+    }
+
+    fn type_char_noop_with_config(
+        char_typed: char,
+        ra_fixture_before: &str,
+        config: &TypingConfig,
+    ) {
+        let file_change = do_type_char(char_typed, ra_fixture_before, config);
         assert!(file_change.is_none())
     }
 
@@ -517,6 +536,38 @@ fn f() {
         );
     }
 
+    #[test]
+    fn no_semi_on_eq_when_disabled() {
+        let config = TypingConfig { semicolon_on_eq: false, ..TEST_CONFIG };
+        type_char_noop_with_config(
+            '=',
+            r#"
+fn foo() {
+    let foo $0 1 + 1
+}
+"#,
+            &config,
+        );
+        type_char_noop_with_config(
+            '=',
+            r#"
+fn f() {
+    i $0 0
+}
+"#,
+            &config,
+        );
+        type_char_noop_with_config(
+            '=',
+            r#"
+fn f(a: u8) {
+    a =$0 0;
+}
+"#,
+            &config,
+        );
+    }
+
     #[test]
     fn assign_to_eq() {
         type_char(
@@ -726,6 +777,21 @@ $0
         );
     }
 
+    #[test]
+    fn no_chain_indent_when_disabled() {
+        let config = TypingConfig { chain_indent: false, ..TEST_CONFIG };
+        type_char_noop_with_config(
+            '.',
+            r#"
+fn main() {
+    xs.foo()
+    $0
+}
+            "#,
+            &config,
+        );
+    }
+
     #[test]
     fn adds_space_after_return_type() {
         type_char(
@@ -20,6 +20,7 @@ use text_edit::TextEdit;
 // - kbd:[Enter] inside triple-slash comments automatically inserts `///`
 // - kbd:[Enter] in the middle or after a trailing space in `//` inserts `//`
 // - kbd:[Enter] inside `//!` doc comments automatically inserts `//!`
+// - kbd:[Enter] inside a `/* */` block comment continues the leading `*` of the current line
 // - kbd:[Enter] after `{` indents contents and closing `}` of single-line block
 //
 // This action needs to be assigned to shortcut explicitly.
@@ -87,7 +88,7 @@ fn on_enter_in_comment(
     offset: TextSize,
 ) -> Option<TextEdit> {
     if comment.kind().shape.is_block() {
-        return None;
+        return on_enter_in_block_comment(comment, file, offset);
     }
 
     let prefix = comment.prefix();
@@ -120,6 +121,34 @@ fn on_enter_in_comment(
     Some(edit)
 }
 
+fn on_enter_in_block_comment(
+    comment: &ast::Comment,
+    file: &ast::SourceFile,
+    offset: TextSize,
+) -> Option<TextEdit> {
+    let comment_range = comment.syntax().text_range();
+    let prefix = comment.prefix();
+    if offset < comment_range.start() + TextSize::of(prefix) {
+        return None;
+    }
+    let text = comment.text();
+    let is_closed = text.len() >= prefix.len() + "*/".len() && text.ends_with("*/");
+    if is_closed && offset > comment_range.end() - TextSize::of("*/") {
+        return None;
+    }
+
+    let indent = node_indent(file, comment.syntax())?;
+    let line_start =
+        text[..(offset - comment_range.start()).into()].rfind('\n').map_or(0, |it| it + 1);
+    // Only continue the `*` column if the comment already uses one.
+    let edit = if text[line_start..].trim_start().starts_with('*') {
+        format!("\n{indent} * $0")
+    } else {
+        format!("\n{indent}$0")
+    };
+    Some(TextEdit::insert(offset, edit))
+}
+
 fn on_enter_in_block(block: ast::BlockExpr, position: FilePosition) -> Option<TextEdit> {
     let contents = block_contents(&block)?;
 
@@ -264,6 +293,102 @@ fn foo() {
         );
     }
 
+    #[test]
+    fn continues_block_comment() {
+        do_check(
+            r"
+/* Some$0 comment */
+fn foo() {}
+",
+            r"
+/* Some
+$0 comment */
+fn foo() {}
+",
+        );
+
+        do_check(
+            r"
+impl S {
+    /**
+     * Some docs.$0
+     */
+    fn foo() {}
+}
+",
+            r"
+impl S {
+    /**
+     * Some docs.
+     * $0
+     */
+    fn foo() {}
+}
+",
+        );
+
+        do_check(
+            r"
+fn foo() {
+    /*! Docs$0*/
+}
+",
+            r"
+fn foo() {
+    /*! Docs
+    $0*/
+}
+",
+        );
+    }
+
+    #[test]
+    fn does_not_insert_star_without_leading_star() {
+        do_check(
+            r"
+fn foo() {
+    /*
+       Some$0 comment
+    */
+}
+",
+            r"
+fn foo() {
+    /*
+       Some
+    $0 comment
+    */
+}
+",
+        );
+
+        do_check(
+            r"
+fn foo() {
+    /*!
+       Docs$0
+    */
+}
+",
+            r"
+fn foo() {
+    /*!
+       Docs
+    $0
+    */
+}
+",
+        );
+    }
+
+    #[test]
+    fn does_not_continue_outside_block_comment() {
+        do_check_noop(r"$0/* comment */");
+        do_check_noop(r"/$0* comment */");
+        do_check_noop(r"/* comment */$0");
+        do_check_noop(r"/* comment *$0/");
+    }
+
     #[test]
     fn does_not_continue_before_doc_comment() {
         do_check_noop(r"$0//! docz");
@@ -5,9 +5,9 @@
 
 use std::{
     borrow::Borrow,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     ops,
-    path::{Component, Path, PathBuf},
+    path::{Component, Path, PathBuf, PrefixComponent},
 };
 
 /// Wrapper around an absolute [`PathBuf`].
@@ -149,6 +149,9 @@ impl AbsPath {
     /// - Removes repeated separators: `/a//b` becomes `/a/b`
     /// - Removes occurrences of `.` and resolves `..`.
     /// - Removes trailing slashes: `/a/b/` becomes `/a/b`.
+    /// - On Windows, lower-cases the drive letter and strips the verbatim
+    ///   prefix: `\\?\C:\a` becomes `c:\a`, `\\?\UNC\server\share` becomes
+    ///   `\\server\share`.
     ///
     /// # Example
     /// ```
@@ -286,9 +289,9 @@ impl RelPath {
 /// Taken from <https://github.com/rust-lang/cargo/blob/79c769c3d7b4c2cf6a93781575b7f592ef974255/src/cargo/util/paths.rs#L60-L85>
 fn normalize_path(path: &Path) -> PathBuf {
     let mut components = path.components().peekable();
-    let mut ret = if let Some(c @ Component::Prefix(..)) = components.peek().copied() {
+    let mut ret = if let Some(Component::Prefix(prefix)) = components.peek().copied() {
         components.next();
-        PathBuf::from(c.as_os_str())
+        PathBuf::from(normalize_prefix(prefix))
     } else {
         PathBuf::new()
     };
@@ -310,3 +313,54 @@ fn normalize_path(path: &Path) -> PathBuf {
     }
     ret
 }
+
+/// The same path can be spelled with different Windows prefixes, for example
+/// `cargo metadata` reports `C:\`, while editors send `c:\` and
+/// canonicalization produces `\\?\C:\`. `Path` doesn't consistently treat
+/// those as equal, so we settle on a single spelling.
+fn normalize_prefix(prefix: PrefixComponent<'_>) -> OsString {
+    match prefix.as_os_str().to_str() {
+        Some(it) => normalize_prefix_str(it).into(),
+        None => prefix.as_os_str().to_owned(),
+    }
+}
+
+/// Works on the textual prefix rather than on [`std::path::Prefix`], which is
+/// only ever parsed on Windows, so that this can be tested everywhere.
+fn normalize_prefix_str(prefix: &str) -> String {
+    if let Some(unc) = prefix.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{unc}");
+    }
+    let disk = prefix.strip_prefix(r"\\?\").unwrap_or(prefix);
+    match disk.as_bytes() {
+        [drive, b':'] if drive.is_ascii_alphabetic() => {
+            format!("{}:", drive.to_ascii_lowercase() as char)
+        }
+        _ => prefix.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_prefix_str;
+
+    #[test]
+    fn normalize_drive_letters() {
+        assert_eq!(normalize_prefix_str("C:"), "c:");
+        assert_eq!(normalize_prefix_str("c:"), "c:");
+        assert_eq!(normalize_prefix_str(r"\\?\C:"), "c:");
+        assert_eq!(normalize_prefix_str(r"\\?\d:"), "d:");
+    }
+
+    #[test]
+    fn normalize_unc_shares() {
+        assert_eq!(normalize_prefix_str(r"\\?\UNC\server\share"), r"\\server\share");
+        assert_eq!(normalize_prefix_str(r"\\server\share"), r"\\server\share");
+    }
+
+    #[test]
+    fn keep_other_prefixes() {
+        assert_eq!(normalize_prefix_str(r"\\.\COM1"), r"\\.\COM1");
+        assert_eq!(normalize_prefix_str(r"\\?\pictures"), r"\\?\pictures");
+    }
+}
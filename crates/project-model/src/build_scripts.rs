@@ -64,7 +64,7 @@ impl WorkspaceBuildScripts {
                 cmd
             }
             _ => {
-                let mut cmd = Command::new(toolchain::cargo());
+                let mut cmd = Command::new(toolchain::cargo_with_env(&config.extra_env));
 
                 cmd.args(["check", "--quiet", "--workspace", "--message-format=json"]);
                 cmd.args(&config.extra_args);
@@ -312,6 +312,7 @@ impl WorkspaceBuildScripts {
                                 }
                                 acc
                             };
+                            data.cfgs = cfgs;
                             if !message.env.is_empty() {
                                 data.envs = mem::take(&mut message.env);
                             }
@@ -328,7 +329,6 @@ impl WorkspaceBuildScripts {
                                     data.envs.push(("OUT_DIR".to_string(), out_dir));
                                 }
                                 data.out_dir = Some(out_dir);
-                                data.cfgs = cfgs;
                             }
                         });
                     }
@@ -391,7 +391,7 @@ impl WorkspaceBuildScripts {
         }
         let res = (|| {
             let target_libdir = (|| {
-                let mut cargo_config = Command::new(toolchain::cargo());
+                let mut cargo_config = Command::new(toolchain::cargo_with_env(extra_env));
                 cargo_config.envs(extra_env);
                 cargo_config
                     .current_dir(current_dir)
@@ -32,6 +32,10 @@ pub struct CargoWorkspace {
     packages: Arena<PackageData>,
     targets: Arena<TargetData>,
     workspace_root: AbsPathBuf,
+    target_directory: AbsPathBuf,
+    /// Canonical roots of the member targets, where they differ from the ones
+    /// reported by `cargo metadata`.
+    canonical_roots: FxHashMap<AbsPathBuf, Target>,
 }
 
 impl ops::Index<Package> for CargoWorkspace {
@@ -151,10 +155,10 @@ pub struct PackageData {
     pub manifest: ManifestPath,
     /// Targets provided by the crate (lib, bin, example, test, ...)
     pub targets: Vec<Target>,
-    /// Does this package come from the local filesystem (and is editable)?
-    pub is_local: bool,
     /// Whether this package is a member of the workspace
     pub is_member: bool,
+    /// Where this package comes from
+    pub origin: PackageOrigin,
     /// List of packages this package depends on
     pub dependencies: Vec<PackageDependency>,
     /// Rust edition for this package
@@ -169,6 +173,36 @@ pub struct PackageData {
     pub metadata: RustAnalyzerPackageMetaData,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PackageOrigin {
+    /// A member of the current workspace.
+    Member,
+    /// A package on the local filesystem outside of the workspace, pulled in as a path dependency.
+    PathDep,
+    /// A package downloaded from a registry, like crates.io.
+    Registry,
+    /// A package checked out from a git repository.
+    Git,
+}
+
+impl PackageOrigin {
+    /// Does this package come from the local filesystem (and is editable)? That
+    /// includes all members of the current workspace, as well as any path
+    /// dependency outside the workspace.
+    pub fn is_local(self) -> bool {
+        matches!(self, PackageOrigin::Member | PackageOrigin::PathDep)
+    }
+
+    fn new(is_member: bool, source: Option<&cargo_metadata::Source>) -> PackageOrigin {
+        match source {
+            _ if is_member => PackageOrigin::Member,
+            None => PackageOrigin::PathDep,
+            Some(source) if source.repr.starts_with("git+") => PackageOrigin::Git,
+            Some(_) => PackageOrigin::Registry,
+        }
+    }
+}
+
 #[derive(Deserialize, Default, Debug, Clone, Eq, PartialEq)]
 pub struct RustAnalyzerPackageMetaData {
     pub rustc_private: bool,
@@ -223,6 +257,8 @@ pub struct TargetData {
     pub root: AbsPathBuf,
     /// Kind of target
     pub kind: TargetKind,
+    /// Rust edition for this target, which may differ from the package's edition
+    pub edition: Edition,
     /// Is this target a proc-macro
     pub is_proc_macro: bool,
     /// Required features of the target without which it won't build
@@ -274,54 +310,44 @@ impl CargoWorkspace {
         progress: &dyn Fn(String),
     ) -> Result<cargo_metadata::Metadata> {
         let targets = find_list_of_build_targets(config, cargo_toml);
-
-        let mut meta = MetadataCommand::new();
-        meta.cargo_path(toolchain::cargo());
-        meta.manifest_path(cargo_toml.to_path_buf());
-        match &config.features {
-            CargoFeatures::All => {
-                meta.features(CargoOpt::AllFeatures);
-            }
-            CargoFeatures::Selected { features, no_default_features } => {
-                if *no_default_features {
-                    meta.features(CargoOpt::NoDefaultFeatures);
-                }
-                if !features.is_empty() {
-                    meta.features(CargoOpt::SomeFeatures(features.clone()));
-                }
-            }
-        }
-        meta.current_dir(current_dir.as_os_str());
-
-        if !targets.is_empty() {
-            let other_options: Vec<_> = targets
-                .into_iter()
-                .flat_map(|target| ["--filter-platform".to_string(), target])
-                .collect();
-            meta.other_options(other_options);
-        }
+        let filters_platform = !targets.is_empty();
+        let mut meta = metadata_command(cargo_toml, current_dir, config, targets);
 
         // FIXME: Fetching metadata is a slow process, as it might require
         // calling crates.io. We should be reporting progress here, but it's
         // unclear whether cargo itself supports it.
         progress("metadata".to_string());
 
-        (|| -> Result<cargo_metadata::Metadata, cargo_metadata::Error> {
-            let mut command = meta.cargo_command();
-            command.envs(&config.extra_env);
-            let output = command.output()?;
-            if !output.status.success() {
-                return Err(cargo_metadata::Error::CargoMetadata {
-                    stderr: String::from_utf8(output.stderr)?,
-                });
+        let exec =
+            |meta: &MetadataCommand| -> Result<cargo_metadata::Metadata, cargo_metadata::Error> {
+                let mut command = meta.cargo_command();
+                command.envs(&config.extra_env);
+                // Spawned through stdx, so that a hanging cargo can be killed on exit.
+                let output =
+                    stdx::process::spawn_with_streaming_output(command, &mut |_| (), &mut |_| ())?;
+                if !output.status.success() {
+                    return Err(cargo_metadata::Error::CargoMetadata {
+                        stderr: String::from_utf8(output.stderr)?,
+                    });
+                }
+                let stdout = from_utf8(&output.stdout)?
+                    .lines()
+                    .find(|line| line.starts_with('{'))
+                    .ok_or(cargo_metadata::Error::NoJson)?;
+                cargo_metadata::MetadataCommand::parse(stdout)
+            };
+
+        let mut res = exec(&meta);
+        if let Err(cargo_metadata::Error::CargoMetadata { stderr }) = &res {
+            // Old versions of cargo don't know about `--filter-platform`, it's
+            // better to index too many dependencies than none at all.
+            if filters_platform && stderr.contains("--filter-platform") {
+                tracing::info!("cargo does not support --filter-platform, retrying without it");
+                meta = metadata_command(cargo_toml, current_dir, config, Vec::new());
+                res = exec(&meta);
             }
-            let stdout = from_utf8(&output.stdout)?
-                .lines()
-                .find(|line| line.starts_with('{'))
-                .ok_or(cargo_metadata::Error::NoJson)?;
-            cargo_metadata::MetadataCommand::parse(stdout)
-        })()
-        .with_context(|| format!("Failed to run `{:?}`", meta.cargo_command()))
+        }
+        res.with_context(|| format!("Failed to run `{:?}`", meta.cargo_command()))
     }
 
     pub fn new(mut meta: cargo_metadata::Metadata) -> CargoWorkspace {
@@ -347,19 +373,9 @@ impl CargoWorkspace {
                 ..
             } = meta_pkg;
             let meta = from_value::<PackageMetadata>(metadata).unwrap_or_default();
-            let edition = match edition {
-                cargo_metadata::Edition::E2015 => Edition::Edition2015,
-                cargo_metadata::Edition::E2018 => Edition::Edition2018,
-                cargo_metadata::Edition::E2021 => Edition::Edition2021,
-                _ => {
-                    tracing::error!("Unsupported edition `{:?}`", edition);
-                    Edition::CURRENT
-                }
-            };
-            // We treat packages without source as "local" packages. That includes all members of
-            // the current workspace, as well as any path dependency outside the workspace.
-            let is_local = source.is_none();
+            let edition = convert_edition(edition);
             let is_member = ws_members.contains(&id);
+            let origin = PackageOrigin::new(is_member, source.as_ref());
 
             let pkg = packages.alloc(PackageData {
                 id: id.repr.clone(),
@@ -367,8 +383,8 @@ impl CargoWorkspace {
                 version,
                 manifest: AbsPathBuf::assert(manifest_path.into()).try_into().unwrap(),
                 targets: Vec::new(),
-                is_local,
                 is_member,
+                origin,
                 edition,
                 repository,
                 dependencies: Vec::new(),
@@ -379,13 +395,15 @@ impl CargoWorkspace {
             let pkg_data = &mut packages[pkg];
             pkg_by_id.insert(id, pkg);
             for meta_tgt in meta_targets {
-                let cargo_metadata::Target { name, kind, required_features, src_path, .. } =
-                    meta_tgt;
+                let cargo_metadata::Target {
+                    name, kind, required_features, src_path, edition, ..
+                } = meta_tgt;
                 let tgt = targets.alloc(TargetData {
                     package: pkg,
                     name,
                     root: AbsPathBuf::assert(src_path.into()),
                     kind: TargetKind::new(&kind),
+                    edition: convert_edition(edition),
                     is_proc_macro: &*kind == ["proc-macro"],
                     required_features,
                 });
@@ -410,25 +428,59 @@ impl CargoWorkspace {
 
         let workspace_root =
             AbsPathBuf::assert(PathBuf::from(meta.workspace_root.into_os_string()));
+        let target_directory =
+            AbsPathBuf::assert(PathBuf::from(meta.target_directory.into_os_string()));
+
+        // `cargo metadata` might report paths through a symlink, while the client
+        // refers to the resolved file. Canonicalize the member targets once here,
+        // so that looking them up doesn't need to touch the file system.
+        // Normalizing also strips the `\\?\` prefix the canonicalization adds on
+        // Windows.
+        let canonical_roots = packages
+            .iter()
+            .filter(|(_, pkg)| pkg.is_member)
+            .flat_map(|(_, pkg)| &pkg.targets)
+            .filter_map(|&tgt| {
+                let root = &targets[tgt].root;
+                let canonical = root.canonicalize().ok()?.normalize();
+                (&canonical != root).then_some((canonical, tgt))
+            })
+            .collect();
 
-        CargoWorkspace { packages, targets, workspace_root }
+        CargoWorkspace { packages, targets, workspace_root, target_directory, canonical_roots }
     }
 
     pub fn packages(&self) -> impl Iterator<Item = Package> + ExactSizeIterator + '_ {
         self.packages.iter().map(|(id, _pkg)| id)
     }
 
+    /// Returns the packages that are members of this workspace, skipping
+    /// their (transitive) dependencies.
+    pub fn members(&self) -> impl Iterator<Item = Package> + '_ {
+        self.packages().filter(|&pkg| self[pkg].is_member)
+    }
+
     pub fn target_by_root(&self, root: &AbsPath) -> Option<Target> {
-        self.packages()
-            .filter(|&pkg| self[pkg].is_member)
-            .find_map(|pkg| self[pkg].targets.iter().find(|&&it| &self[it].root == root))
-            .copied()
+        let find = |root: &AbsPath| {
+            self.members()
+                .find_map(|pkg| self[pkg].targets.iter().find(|&&it| &self[it].root == root))
+                .or_else(|| self.canonical_roots.get(root))
+                .copied()
+        };
+        // The client might refer to the file through a non-normalized path.
+        find(root).or_else(|| find(&root.normalize()))
     }
 
     pub fn workspace_root(&self) -> &AbsPath {
         &self.workspace_root
     }
 
+    /// Returns the directory cargo puts build artifacts into, which might have
+    /// been moved away from `target/` with `CARGO_TARGET_DIR` or `build.target-dir`.
+    pub fn target_directory(&self) -> &AbsPath {
+        &self.target_directory
+    }
+
     pub fn package_flag(&self, package: &PackageData) -> String {
         if self.is_unique(&package.name) {
             package.name.clone()
@@ -472,6 +524,61 @@ impl CargoWorkspace {
     }
 }
 
+fn convert_edition(edition: cargo_metadata::Edition) -> Edition {
+    match edition {
+        cargo_metadata::Edition::E2015 => Edition::Edition2015,
+        cargo_metadata::Edition::E2018 => Edition::Edition2018,
+        cargo_metadata::Edition::E2021 => Edition::Edition2021,
+        _ => {
+            tracing::error!("Unsupported edition `{:?}`", edition);
+            Edition::CURRENT
+        }
+    }
+}
+
+/// Builds the `cargo metadata` invocation for the given manifest and
+/// `--filter-platform` targets.
+pub(crate) fn metadata_command(
+    cargo_toml: &ManifestPath,
+    current_dir: &AbsPath,
+    config: &CargoConfig,
+    targets: Vec<String>,
+) -> MetadataCommand {
+    let mut meta = MetadataCommand::new();
+    meta.cargo_path(toolchain::cargo_with_env(&config.extra_env));
+    meta.manifest_path(cargo_toml.to_path_buf());
+    match &config.features {
+        CargoFeatures::All => {
+            meta.features(CargoOpt::AllFeatures);
+        }
+        CargoFeatures::Selected { features, no_default_features } => {
+            if *no_default_features {
+                meta.features(CargoOpt::NoDefaultFeatures);
+            }
+            if !features.is_empty() {
+                meta.features(CargoOpt::SomeFeatures(features.clone()));
+            }
+        }
+    }
+    meta.current_dir(current_dir.as_os_str());
+
+    let mut other_options: Vec<_> =
+        targets.into_iter().flat_map(|target| ["--filter-platform".to_string(), target]).collect();
+    // `cargo metadata` rejects most of the flags other cargo commands accept, so
+    // only forward the ones that keep it from touching the network or the lockfile.
+    other_options.extend(
+        config
+            .extra_args
+            .iter()
+            .filter(|arg| matches!(arg.as_str(), "--offline" | "--frozen" | "--locked"))
+            .cloned(),
+    );
+    if !other_options.is_empty() {
+        meta.other_options(other_options);
+    }
+    meta
+}
+
 fn find_list_of_build_targets(config: &CargoConfig, cargo_toml: &ManifestPath) -> Vec<String> {
     if let Some(target) = &config.target {
         return [target.into()].to_vec();
@@ -516,7 +623,7 @@ fn cargo_config_build_target(
     cargo_toml: &ManifestPath,
     extra_env: &FxHashMap<String, String>,
 ) -> Vec<String> {
-    let mut cargo_config = Command::new(toolchain::cargo());
+    let mut cargo_config = Command::new(toolchain::cargo_with_env(extra_env));
     cargo_config.envs(extra_env);
     cargo_config
         .current_dir(cargo_toml.parent())
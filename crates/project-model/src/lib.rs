@@ -33,6 +33,7 @@ mod tests;
 use std::{
     fs::{self, read_dir, ReadDir},
     io,
+    path::Path,
     process::Command,
 };
 
@@ -44,7 +45,7 @@ pub use crate::{
     build_scripts::WorkspaceBuildScripts,
     cargo_workspace::{
         CargoConfig, CargoFeatures, CargoWorkspace, Package, PackageData, PackageDependency,
-        RustLibSource, Target, TargetData, TargetKind, UnsetTestCrates,
+        PackageOrigin, RustLibSource, Target, TargetData, TargetKind, UnsetTestCrates,
     },
     manifest_path::ManifestPath,
     project_json::{ProjectJson, ProjectJsonData},
@@ -92,9 +93,108 @@ impl ProjectManifest {
             .map(|paths| paths.into_iter().map(ProjectManifest::CargoToml).collect());
 
         fn find_cargo_toml(path: &AbsPath) -> io::Result<Vec<ManifestPath>> {
-            match find_in_parent_dirs(path, "Cargo.toml") {
-                Some(it) => Ok(vec![it]),
-                None => Ok(find_cargo_toml_in_child_dir(read_dir(path)?)),
+            let manifests = match find_in_parent_dirs(path, "Cargo.toml") {
+                Some(it) => vec![it],
+                None => find_cargo_toml_in_child_dir(read_dir(path)?),
+            };
+            Ok(manifests.into_iter().map(find_enclosing_workspace).collect())
+        }
+
+        /// The nearest `Cargo.toml` may belong to a member of a workspace further
+        /// up, in which case we want to load the whole workspace so that the
+        /// sibling members are available too.
+        ///
+        /// Like cargo, we take the closest parent manifest with a `[workspace]`
+        /// table, provided that its `members` and `exclude` lists include the
+        /// package. This is only a text scan, as running cargo here would block.
+        fn find_enclosing_workspace(manifest: ManifestPath) -> ManifestPath {
+            if workspace_section(&manifest).is_some() {
+                return manifest;
+            }
+            let package_dir = manifest.parent();
+            let workspace = std::iter::successors(package_dir.parent(), |it| it.parent())
+                .filter_map(|dir| ManifestPath::try_from(dir.join("Cargo.toml")).ok())
+                .find_map(|it| Some((workspace_section(&it)?, it)));
+            let Some((section, workspace)) = workspace else { return manifest };
+            let Some(package_dir) = package_dir.strip_prefix(workspace.parent()) else {
+                return manifest;
+            };
+            let package_dir = package_dir.as_ref();
+            let excluded = string_array(&section, "exclude")
+                .iter()
+                .any(|it| package_dir.starts_with(it.trim_start_matches("./")));
+            let member = string_array(&section, "members")
+                .iter()
+                .any(|pattern| matches_member(pattern, package_dir));
+            if member && !excluded {
+                workspace
+            } else {
+                manifest
+            }
+        }
+
+        /// Returns the text of the `[workspace]` table of `manifest`, if it has one.
+        fn workspace_section(manifest: &ManifestPath) -> Option<String> {
+            let text = fs::read_to_string(manifest).ok()?;
+            let mut lines = text.lines();
+            lines.find(|it| it.trim() == "[workspace]")?;
+            let section = lines.take_while(|it| !it.trim_start().starts_with('['));
+            Some(section.collect::<Vec<_>>().join("\n"))
+        }
+
+        /// Reads the strings of the array `key = [..]` in a toml table, e.g. the
+        /// `members` of a workspace.
+        fn string_array(table: &str, key: &str) -> Vec<String> {
+            let mut lines = table.lines();
+            let Some(first) = lines
+                .find_map(|line| line.trim_start().strip_prefix(key)?.trim_start().strip_prefix('='))
+            else {
+                return Vec::new();
+            };
+            let mut value = first.to_owned();
+            for line in lines {
+                if value.contains(']') {
+                    break;
+                }
+                value.push_str(line);
+            }
+            let Some(value) = value.trim_start().strip_prefix('[') else { return Vec::new() };
+            let value = value.split(']').next().unwrap_or_default();
+            value.split('"').skip(1).step_by(2).map(ToOwned::to_owned).collect()
+        }
+
+        /// Matches a package directory, relative to the workspace root, against
+        /// one of the `members` globs.
+        fn matches_member(pattern: &str, package_dir: &Path) -> bool {
+            let mut pattern =
+                pattern.trim_start_matches("./").split('/').filter(|it| !it.is_empty());
+            let mut components = package_dir.components().map(|it| it.as_os_str().to_str());
+            loop {
+                match (pattern.next(), components.next()) {
+                    (None, None) => return true,
+                    (Some(pattern), Some(Some(component))) if glob_match(pattern, component) => {}
+                    _ => return false,
+                }
+            }
+        }
+
+        /// Matches a single path component against a pattern with `*` and `?`
+        /// wildcards.
+        fn glob_match(pattern: &str, text: &str) -> bool {
+            match pattern.chars().next() {
+                None => text.is_empty(),
+                Some('*') => (0..=text.len())
+                    .filter(|&it| text.is_char_boundary(it))
+                    .any(|it| glob_match(&pattern[1..], &text[it..])),
+                Some(c) => {
+                    let mut chars = text.chars();
+                    match chars.next() {
+                        Some(t) if c == '?' || c == t => {
+                            glob_match(&pattern[c.len_utf8()..], chars.as_str())
+                        }
+                        _ => false,
+                    }
+                }
             }
         }
 
@@ -49,8 +49,9 @@
 //! user explores them belongs to that extension (it's totally valid to change
 //! rust-project.json over time via configuration request!)
 
-use std::path::PathBuf;
+use std::{fs, path::PathBuf};
 
+use anyhow::{bail, Result};
 use base_db::{CrateDisplayName, CrateId, CrateName, Dependency, Edition};
 use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::FxHashMap;
@@ -169,6 +170,36 @@ impl ProjectJson {
     pub fn path(&self) -> &AbsPath {
         &self.project_root
     }
+
+    /// Checks that every dependency refers to a crate listed in the project and
+    /// that every crate's root module exists on disk.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        for (idx, krate) in self.crates.iter().enumerate() {
+            let name = match &krate.display_name {
+                Some(name) => format!("crate #{idx} (`{name}`)"),
+                None => format!("crate #{idx}"),
+            };
+            if fs::metadata(&krate.root_module).is_err() {
+                errors.push(format!(
+                    "{name} has a root module which does not exist: {}",
+                    krate.root_module.display()
+                ));
+            }
+            for dep in &krate.deps {
+                if dep.crate_id.0 as usize >= self.crates.len() {
+                    errors.push(format!(
+                        "{name} depends on `{}`, but there is no crate #{}",
+                        dep.name, dep.crate_id.0
+                    ));
+                }
+            }
+        }
+        if !errors.is_empty() {
+            bail!("invalid rust-project.json:\n{}", errors.join("\n"));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -46,7 +46,7 @@ fn get_rust_cfgs(
     extra_env: &FxHashMap<String, String>,
 ) -> Result<String> {
     if let Some(cargo_toml) = cargo_toml {
-        let mut cargo_config = Command::new(toolchain::cargo());
+        let mut cargo_config = Command::new(toolchain::cargo_with_env(extra_env));
         cargo_config.envs(extra_env);
         cargo_config
             .current_dir(cargo_toml.parent())
@@ -103,7 +103,11 @@ impl Sysroot {
 
     pub fn with_sysroot_dir(sysroot_dir: AbsPathBuf) -> Result<Sysroot> {
         let sysroot_src_dir = discover_sysroot_src_dir(&sysroot_dir).ok_or_else(|| {
-            format_err!("can't load standard library from sysroot {}", sysroot_dir.display())
+            format_err!(
+                "can't load standard library from sysroot {}\n{}",
+                sysroot_dir.display(),
+                INSTALL_RUST_SRC_HINT,
+            )
         })?;
         Ok(Sysroot::load(sysroot_dir, sysroot_src_dir))
     }
@@ -224,8 +228,9 @@ fn discover_sysroot_src_dir_or_add_component(
 can't load standard library from sysroot
 {}
 (discovered via `rustc --print sysroot`)
-try installing the Rust source the same way you installed rustc",
+{}",
                 sysroot_path.display(),
+                INSTALL_RUST_SRC_HINT,
             )
         })
 }
@@ -251,6 +256,9 @@ fn get_rust_src(sysroot_path: &AbsPath) -> Option<AbsPathBuf> {
     }
 }
 
+const INSTALL_RUST_SRC_HINT: &str = "try installing the Rust source the same way you installed \
+rustc (with rustup, run `rustup component add rust-src`)";
+
 const SYSROOT_CRATES: &str = "
 alloc
 backtrace
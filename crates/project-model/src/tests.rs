@@ -10,8 +10,9 @@ use paths::{AbsPath, AbsPathBuf};
 use serde::de::DeserializeOwned;
 
 use crate::{
-    CargoWorkspace, CfgOverrides, ProjectJson, ProjectJsonData, ProjectWorkspace, Sysroot,
-    WorkspaceBuildScripts,
+    cargo_workspace::metadata_command, CargoConfig, CargoFeatures, CargoWorkspace, CfgOverrides,
+    ManifestPath, ProjectJson, ProjectJsonData, ProjectManifest, ProjectWorkspace, Sysroot,
+    TargetKind, WorkspaceBuildScripts,
 };
 
 fn load_cargo(file: &str) -> (CrateGraph, ProcMacroPaths) {
@@ -22,10 +23,12 @@ fn load_cargo_with_overrides(
     file: &str,
     cfg_overrides: CfgOverrides,
 ) -> (CrateGraph, ProcMacroPaths) {
-    let meta = get_test_json_file(file);
-    let cargo_workspace = CargoWorkspace::new(meta);
-    let project_workspace = ProjectWorkspace::Cargo {
-        cargo: cargo_workspace,
+    to_crate_graph(load_cargo_project_workspace(file, cfg_overrides))
+}
+
+fn load_cargo_project_workspace(file: &str, cfg_overrides: CfgOverrides) -> ProjectWorkspace {
+    ProjectWorkspace::Cargo {
+        cargo: load_cargo_workspace(file),
         build_scripts: WorkspaceBuildScripts::default(),
         sysroot: Err(None),
         rustc: Err(None),
@@ -33,16 +36,25 @@ fn load_cargo_with_overrides(
         cfg_overrides,
         toolchain: None,
         target_layout: Err("target_data_layout not loaded".into()),
-    };
-    to_crate_graph(project_workspace)
+    }
+}
+
+fn load_cargo_workspace(file: &str) -> CargoWorkspace {
+    let meta = get_test_json_file(file);
+    CargoWorkspace::new(meta)
 }
 
 fn load_rust_project(file: &str) -> (CrateGraph, ProcMacroPaths) {
     let data = get_test_json_file(file);
     let project = rooted_project_json(data);
     let sysroot = Ok(get_fake_sysroot());
-    let project_workspace =
-        ProjectWorkspace::Json { project, sysroot, rustc_cfg: Vec::new(), toolchain: None };
+    let project_workspace = ProjectWorkspace::Json {
+        project,
+        sysroot,
+        rustc_cfg: Vec::new(),
+        toolchain: None,
+        validation_error: None,
+    };
     to_crate_graph(project_workspace)
 }
 
@@ -1800,3 +1812,474 @@ fn rust_project_is_proc_macro_has_proc_macro_dep() {
     // on the proc_macro sysroot crate.
     crate_data.dependencies.iter().find(|&dep| dep.name.deref() == "proc_macro").unwrap();
 }
+
+#[test]
+fn cargo_metadata_command_with_selected_features() {
+    let manifest_path = get_test_path("conflicting-features/Cargo.toml");
+    let manifest = ManifestPath::try_from(AbsPathBuf::assert(manifest_path.clone())).unwrap();
+    let features =
+        CargoFeatures::Selected { features: vec!["backend-b".into()], no_default_features: true };
+    let config = CargoConfig { features, ..CargoConfig::default() };
+    let command = metadata_command(&manifest, manifest.parent(), &config, Vec::new());
+    let args = command
+        .cargo_command()
+        .get_args()
+        .map(|arg| arg.to_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        [
+            "metadata",
+            "--format-version",
+            "1",
+            "--features",
+            "backend-b",
+            "--no-default-features",
+            "--manifest-path",
+            manifest_path.to_str().unwrap(),
+        ]
+    );
+
+    let config = CargoConfig { features: CargoFeatures::All, ..CargoConfig::default() };
+    let command = metadata_command(&manifest, manifest.parent(), &config, Vec::new());
+    let args = command.cargo_command().get_args().map(ToOwned::to_owned).collect::<Vec<_>>();
+    assert!(args.iter().any(|arg| arg == "--all-features"));
+    assert!(!args.iter().any(|arg| arg == "--features" || arg == "--no-default-features"));
+}
+
+#[test]
+fn cargo_metadata_command_forwards_offline_flags() {
+    let manifest =
+        ManifestPath::try_from(AbsPathBuf::assert(get_test_path("hello/Cargo.toml"))).unwrap();
+    let extra_args = ["--offline", "--release", "--locked"].map(String::from).to_vec();
+    let config = CargoConfig { extra_args, ..CargoConfig::default() };
+    let command = metadata_command(
+        &manifest,
+        manifest.parent(),
+        &config,
+        vec!["x86_64-unknown-linux-gnu".into()],
+    );
+    let args = command
+        .cargo_command()
+        .get_args()
+        .map(|arg| arg.to_str().unwrap().to_owned())
+        .skip_while(|arg| arg != "--filter-platform")
+        .collect::<Vec<_>>();
+    assert_eq!(args, ["--filter-platform", "x86_64-unknown-linux-gnu", "--offline", "--locked"]);
+}
+
+#[test]
+fn cargo_metadata_command_uses_cargo_from_extra_env() {
+    let manifest =
+        ManifestPath::try_from(AbsPathBuf::assert(get_test_path("hello/Cargo.toml"))).unwrap();
+    let mut config = CargoConfig::default();
+    config.extra_env.insert("CARGO".into(), "/opt/sandbox/cargo-wrapper".into());
+    let command = metadata_command(&manifest, manifest.parent(), &config, Vec::new());
+    assert_eq!(command.cargo_command().get_program(), "/opt/sandbox/cargo-wrapper");
+}
+
+#[test]
+fn cargo_conflicting_features_only_enables_resolved_features() {
+    let (crate_graph, _proc_macros) = load_cargo("conflicting-features-metadata.json");
+    let crate_id = crate_graph.iter().next().unwrap();
+    let crate_data = &crate_graph[crate_id];
+    let enabled = crate_data.cfg_options.get_cfg_values("feature").collect::<Vec<_>>();
+    assert_eq!(enabled, ["backend-b"]);
+
+    let mut potential = crate_data
+        .potential_cfg_options
+        .as_ref()
+        .unwrap()
+        .get_cfg_values("feature")
+        .map(|it| it.as_str())
+        .collect::<Vec<_>>();
+    potential.sort();
+    assert_eq!(potential, ["backend-a", "backend-b", "default"]);
+}
+
+#[test]
+fn cargo_workspace_dependency_edges() {
+    let cargo = load_cargo_workspace("workspace-metadata.json");
+    let mut actual = String::new();
+    for pkg in cargo.packages() {
+        let deps = cargo[pkg]
+            .dependencies
+            .iter()
+            .map(|dep| format!("{} ({}, {:?})", dep.name, cargo[dep.pkg].name, dep.kind))
+            .collect::<Vec<_>>();
+        actual += &format!("{}: [{}]\n", cargo[pkg].name, deps.join(", "));
+    }
+    expect![[r#"
+        libc: []
+        member-a: [libc (libc, Normal), member_b (member-b, Normal)]
+        member-b: [member_a (member-a, Dev)]
+    "#]]
+    .assert_eq(&actual);
+
+    // The dev-dependency of `member-b` on `member-a` would close a cycle, so
+    // lowering must drop that edge rather than loop forever.
+    let (crate_graph, _proc_macros) = load_cargo("workspace-metadata.json");
+    let name = |krate| crate_graph[krate].display_name.as_ref().unwrap().to_string();
+    let mut lines = crate_graph
+        .iter()
+        .map(|krate| {
+            let mut deps = crate_graph
+                .transitive_deps(krate)
+                .filter(|&dep| dep != krate)
+                .map(name)
+                .collect::<Vec<_>>();
+            deps.sort();
+            deps.dedup();
+            format!("{}: [{}]\n", name(krate), deps.join(", "))
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+    expect![[r#"
+        demo: [libc, member_a, member_b]
+        integration: [libc, member_a, member_b]
+        libc: []
+        member_a: [libc, member_b]
+        member_b: []
+    "#]]
+    .assert_eq(&lines.concat());
+}
+
+#[test]
+fn cargo_virtual_manifest_workspace() {
+    let cargo = load_cargo_workspace("workspace-metadata.json");
+    // The root `Cargo.toml` only has a `[workspace]` section, so no package lives there.
+    assert!(cargo.workspace_root().as_ref().ends_with("workspace"));
+    assert!(cargo.packages().all(|pkg| cargo[pkg].manifest.parent() != cargo.workspace_root()));
+
+    let mut members = cargo.members().map(|pkg| cargo[pkg].name.as_str()).collect::<Vec<_>>();
+    members.sort();
+    assert_eq!(members, ["member-a", "member-b"]);
+
+    for pkg in cargo.packages() {
+        for &tgt in &cargo[pkg].targets {
+            let found = cargo.target_by_root(&cargo[tgt].root);
+            assert_eq!(found, cargo[pkg].is_member.then_some(tgt), "{}", cargo[tgt].name);
+        }
+    }
+}
+
+#[test]
+fn cargo_target_by_non_normalized_root() {
+    let cargo = load_cargo_workspace("workspace-metadata.json");
+    let root = cargo.workspace_root().join("member-b/../member-a/./src/lib.rs");
+    let tgt = cargo.target_by_root(&root).unwrap();
+    assert_eq!(cargo[tgt].name, "member-a");
+}
+
+#[test]
+#[cfg(unix)]
+fn cargo_target_by_canonical_root() {
+    let dir = std::env::temp_dir().join(format!("ra-canonical-root-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let lib = dir.join("real/workspace/member-a/src/lib.rs");
+    std::fs::create_dir_all(lib.parent().unwrap()).unwrap();
+    std::fs::write(&lib, "").unwrap();
+    std::os::unix::fs::symlink(dir.join("real"), dir.join("link")).unwrap();
+
+    // `cargo metadata` reports the paths through the symlink.
+    let data = std::fs::read_to_string(get_test_path("workspace-metadata.json")).unwrap();
+    let data = data.replace("$ROOT$", &format!("{}/link/", dir.display()));
+    let cargo = CargoWorkspace::new(serde_json::from_str(&data).unwrap());
+
+    let lib = AbsPathBuf::assert(std::fs::canonicalize(lib).unwrap());
+    let tgt = cargo.target_by_root(&lib).unwrap();
+    assert_eq!(cargo[tgt].name, "member-a");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cargo_package_origins() {
+    let cargo = load_cargo_workspace("package-origins-metadata.json");
+    let mut origins = cargo
+        .packages()
+        .map(|pkg| format!("{}: {:?}", cargo[pkg].name, cargo[pkg].origin))
+        .collect::<Vec<_>>();
+    origins.sort();
+    expect![[r#"
+        [
+            "app: Member",
+            "local-util: PathDep",
+            "regex: Git",
+            "serde: Registry",
+        ]
+    "#]]
+    .assert_debug_eq(&origins);
+}
+
+#[test]
+fn cargo_package_roots_by_origin() {
+    let workspace =
+        load_cargo_project_workspace("package-origins-metadata.json", CfgOverrides::default());
+    let ProjectWorkspace::Cargo { cargo, .. } = &workspace else { unreachable!() };
+    let mut roots = workspace
+        .to_roots()
+        .into_iter()
+        .map(|root| {
+            let pkg =
+                cargo.packages().find(|&pkg| cargo[pkg].manifest.parent() == &*root.include[0]);
+            format!("{}: {}", cargo[pkg.unwrap()].name, root.is_local)
+        })
+        .collect::<Vec<_>>();
+    roots.sort();
+    // Only members and path dependencies are editable, registry and git
+    // dependencies are treated as libraries.
+    expect![[r#"
+        [
+            "app: true",
+            "local-util: true",
+            "regex: false",
+            "serde: false",
+        ]
+    "#]]
+    .assert_debug_eq(&roots);
+}
+
+#[test]
+fn cargo_dev_dependency_cycle_keeps_normal_edges() {
+    // `member-a` dev-depends on `member-b`, which depends on `member-a`. Only
+    // the dev-dependency edge from `member-a`'s lib may be dropped, no matter
+    // in which order the packages are visited.
+    let (crate_graph, _proc_macros) = load_cargo("dev-dependency-cycle-metadata.json");
+    let mut deps = crate_graph
+        .iter()
+        .map(|krate| {
+            let krate = &crate_graph[krate];
+            let mut deps =
+                krate.dependencies.iter().map(|dep| dep.name.to_string()).collect::<Vec<_>>();
+            deps.sort();
+            format!("{}: {}", krate.display_name.as_ref().unwrap(), deps.join(", "))
+        })
+        .collect::<Vec<_>>();
+    deps.sort();
+    expect![[r#"
+        [
+            "integration: member_a, member_b",
+            "libc: ",
+            "member_a: ",
+            "member_b: libc, member_a",
+        ]
+    "#]]
+    .assert_debug_eq(&deps);
+}
+
+#[test]
+fn cargo_renamed_and_dashed_dependencies() {
+    // `serde` is renamed to `serde1` in the manifest, and `local-util` is imported as `local_util`.
+    let cargo = load_cargo_workspace("package-origins-metadata.json");
+    let app = cargo.packages().find(|&pkg| cargo[pkg].name == "app").unwrap();
+    let deps = cargo[app]
+        .dependencies
+        .iter()
+        .map(|dep| format!("{} -> {}", dep.name, cargo[dep.pkg].name))
+        .collect::<Vec<_>>();
+    assert_eq!(deps, ["local_util -> local-util", "regex -> regex", "serde1 -> serde"]);
+
+    let (crate_graph, _proc_macros) = load_cargo("package-origins-metadata.json");
+    let app = crate_graph
+        .iter()
+        .find(|&krate| crate_graph[krate].display_name.as_deref() == Some("app"))
+        .unwrap();
+    let deps = crate_graph[app]
+        .dependencies
+        .iter()
+        .map(|dep| {
+            let krate = &crate_graph[dep.crate_id];
+            format!("{} -> {}", dep.name, krate.display_name.as_ref().unwrap())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(deps, ["local_util -> local_util", "regex -> regex", "serde1 -> serde"]);
+}
+
+#[test]
+fn cargo_target_required_features() {
+    let cargo = load_cargo_workspace("workspace-metadata.json");
+    let mut targets = cargo
+        .packages()
+        .flat_map(|pkg| cargo[pkg].targets.iter().map(|&tgt| &cargo[tgt]))
+        .map(|tgt| format!("{} ({:?}): {:?}", tgt.name, tgt.kind, tgt.required_features))
+        .collect::<Vec<_>>();
+    targets.sort();
+    expect![[r#"
+        [
+            "demo (Example): [\"extra\"]",
+            "integration (Test): []",
+            "libc (Lib): []",
+            "member-a (Lib): []",
+            "member-b (Lib): []",
+        ]
+    "#]]
+    .assert_debug_eq(&targets);
+}
+
+#[test]
+fn cargo_proc_macro_target() {
+    let cargo = load_cargo_workspace("proc-macro-metadata.json");
+    let macros = cargo.packages().find(|&pkg| cargo[pkg].name == "app-macros").unwrap();
+    let tgt = &cargo[cargo[macros].targets[0]];
+    assert_eq!(tgt.kind, TargetKind::Lib);
+    assert!(tgt.is_proc_macro);
+
+    let (crate_graph, proc_macros) = load_cargo("proc-macro-metadata.json");
+    let krate = |name| {
+        crate_graph.iter().find(|&krate| crate_graph[krate].display_name.as_deref() == Some(name))
+    };
+    let app = krate("app").unwrap();
+    let macros = krate("app_macros").unwrap();
+    assert!(crate_graph[macros].is_proc_macro);
+    assert!(!crate_graph[app].is_proc_macro);
+    assert!(proc_macros.contains_key(&macros));
+    // Proc-macro crates are regular dependencies, that's how their macros get in scope.
+    assert!(crate_graph[app].dependencies.iter().any(|dep| dep.crate_id == macros));
+}
+
+#[test]
+fn cargo_target_kinds() {
+    let cargo = load_cargo_workspace("proc-macro-metadata.json");
+    let app = cargo.packages().find(|&pkg| cargo[pkg].name == "app").unwrap();
+    let kinds = cargo[app]
+        .targets
+        .iter()
+        .map(|&tgt| format!("{}: {:?}", cargo[tgt].name, cargo[tgt].kind))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, ["app: Lib", "build-script-build: BuildScript", "throughput: Bench"]);
+
+    // The build script is a crate of its own, which can't depend on the library it builds.
+    let (crate_graph, _proc_macros) = load_cargo("proc-macro-metadata.json");
+    let mut deps = crate_graph
+        .iter()
+        .map(|krate| {
+            let krate = &crate_graph[krate];
+            let deps =
+                krate.dependencies.iter().map(|dep| dep.name.to_string()).collect::<Vec<_>>();
+            format!("{}: [{}]", krate.display_name.as_ref().unwrap(), deps.join(", "))
+        })
+        .collect::<Vec<_>>();
+    deps.sort();
+    expect![[r#"
+        [
+            "app: [app_macros]",
+            "app_macros: []",
+            "build_script_build: []",
+            "throughput: [app, app_macros]",
+        ]
+    "#]]
+    .assert_debug_eq(&deps);
+}
+
+#[test]
+fn cargo_target_directory_is_excluded() {
+    let workspace =
+        load_cargo_project_workspace("proc-macro-metadata.json", CfgOverrides::default());
+    let ProjectWorkspace::Cargo { cargo, .. } = &workspace else { unreachable!() };
+    // The fixture was generated with `CARGO_TARGET_DIR=build`.
+    let target_directory = cargo.target_directory().to_path_buf();
+    assert!(cargo.target_directory().as_ref().ends_with("app/build"));
+
+    let app_root = cargo.workspace_root();
+    let roots = workspace.to_roots();
+    let app = roots.iter().find(|root| *root.include[0] == *app_root).unwrap();
+    assert!(app.exclude.contains(&target_directory));
+}
+
+#[test]
+fn cargo_workspace_editions() {
+    let (crate_graph, _proc_macros) = load_cargo("workspace-metadata.json");
+    let mut editions = crate_graph
+        .iter()
+        .map(|krate| {
+            let krate = &crate_graph[krate];
+            format!("{}: {:?}", krate.display_name.as_ref().unwrap(), krate.edition)
+        })
+        .collect::<Vec<_>>();
+    editions.sort();
+    // `integration` overrides the edition of its package in `[[test]]`.
+    expect![[r#"
+        [
+            "demo: Edition2021",
+            "integration: Edition2018",
+            "libc: Edition2015",
+            "member_a: Edition2021",
+            "member_b: Edition2021",
+        ]
+    "#]]
+    .assert_debug_eq(&editions);
+}
+
+#[test]
+fn sysroot_without_sources_reports_rust_src_hint() {
+    let sysroot_dir = AbsPathBuf::assert(get_test_path("no-sysroot"));
+    let err = Sysroot::with_sysroot_dir(sysroot_dir).unwrap_err().to_string();
+    assert!(err.contains("rustup component add rust-src"), "{err}");
+}
+
+#[test]
+fn rust_project_with_invalid_crates_is_rejected() {
+    let data = get_test_json_file("invalid-project.json");
+    let base = AbsPathBuf::assert(get_test_path(""));
+    let project = ProjectJson::new(&base, data);
+    let err = project.validate().unwrap_err().to_string();
+    let lines = err.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{err}");
+    assert_eq!(lines[0], "invalid rust-project.json:");
+    assert_eq!(
+        lines[1],
+        "crate #0 (`hello_world`) depends on `missing_dep`, but there is no crate #2"
+    );
+    assert!(lines[2].starts_with("crate #1 has a root module which does not exist: "), "{err}");
+    assert!(lines[2].ends_with("lib.rs"), "{err}");
+}
+
+fn discovered_manifests(dir: &str) -> Vec<String> {
+    let root = AbsPathBuf::assert(get_test_path("enclosing-workspace"));
+    ProjectManifest::discover(&root.join(dir))
+        .unwrap()
+        .into_iter()
+        .map(|it| match it {
+            ProjectManifest::CargoToml(it) | ProjectManifest::ProjectJson(it) => {
+                it.strip_prefix(&root).unwrap().as_ref().to_str().unwrap().replace('\\', "/")
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn discovery_prefers_enclosing_workspace() {
+    assert_eq!(discovered_manifests("crates/member"), ["Cargo.toml"]);
+    assert_eq!(discovered_manifests("tools/cli"), ["Cargo.toml"]);
+}
+
+#[test]
+fn discovery_falls_back_to_nearest_manifest() {
+    assert_eq!(discovered_manifests("crates/excluded"), ["crates/excluded/Cargo.toml"]);
+    assert_eq!(discovered_manifests("stray"), ["stray/Cargo.toml"]);
+    // Matches the members, but is a workspace of its own.
+    assert_eq!(discovered_manifests("crates/nested"), ["crates/nested/Cargo.toml"]);
+}
+
+#[test]
+fn rust_project_with_invalid_crates_still_loads() {
+    let data = get_test_json_file("invalid-project.json");
+    let base = AbsPathBuf::assert(get_test_path(""));
+    let project = ProjectJson::new(&base, data);
+    let workspace = ProjectWorkspace::load_inline(project, None, &Default::default(), None);
+    let ProjectWorkspace::Json { validation_error, .. } = &workspace else { unreachable!() };
+    let err = validation_error.as_deref().unwrap();
+    assert!(err.starts_with("invalid rust-project.json:"), "{err}");
+
+    // Only the crate with the missing root module is left out, the dangling
+    // dependency is skipped.
+    let (crate_graph, _proc_macros) = workspace.to_crate_graph(
+        &mut |path| std::fs::metadata(path).is_ok().then_some(FileId(0)),
+        &Default::default(),
+    );
+    let krates = crate_graph.iter().collect::<Vec<_>>();
+    assert_eq!(krates.len(), 1);
+    let krate = &crate_graph[krates[0]];
+    assert_eq!(krate.display_name.as_ref().unwrap().to_string(), "hello_world");
+    assert!(krate.dependencies.is_empty());
+}
@@ -23,7 +23,7 @@ use crate::{
     rustc_cfg,
     sysroot::SysrootCrate,
     target_data_layout, utf8_stdout, CargoConfig, CargoWorkspace, InvocationStrategy, ManifestPath,
-    Package, ProjectJson, ProjectManifest, Sysroot, TargetKind, WorkspaceBuildScripts,
+    Package, ProjectJson, ProjectManifest, Sysroot, TargetData, TargetKind, WorkspaceBuildScripts,
 };
 
 /// A set of cfg-overrides per crate.
@@ -90,6 +90,9 @@ pub enum ProjectWorkspace {
         /// `rustc --print cfg`.
         rustc_cfg: Vec<CfgFlag>,
         toolchain: Option<Version>,
+        /// The problems found by [`ProjectJson::validate`]. The valid parts of
+        /// the project are loaded nonetheless.
+        validation_error: Option<String>,
     },
     // FIXME: The primary limitation of this approach is that the set of detached files needs to be fixed at the beginning.
     // That's not the end user experience we should strive for.
@@ -137,7 +140,7 @@ impl fmt::Debug for ProjectWorkspace {
                 .field("toolchain", &toolchain)
                 .field("data_layout", &data_layout)
                 .finish(),
-            ProjectWorkspace::Json { project, sysroot, rustc_cfg, toolchain } => {
+            ProjectWorkspace::Json { project, sysroot, rustc_cfg, toolchain, validation_error } => {
                 let mut debug_struct = f.debug_struct("Json");
                 debug_struct.field("n_crates", &project.n_crates());
                 if let Ok(sysroot) = sysroot {
@@ -145,6 +148,7 @@ impl fmt::Debug for ProjectWorkspace {
                 }
                 debug_struct.field("toolchain", &toolchain);
                 debug_struct.field("n_rustc_cfg", &rustc_cfg.len());
+                debug_struct.field("valid", &validation_error.is_none());
                 debug_struct.finish()
             }
             ProjectWorkspace::DetachedFiles { files, sysroot, rustc_cfg } => f
@@ -196,7 +200,11 @@ impl ProjectWorkspace {
                 )
             }
             ProjectManifest::CargoToml(cargo_toml) => {
-                let toolchain = version(cargo_toml.parent(), toolchain::cargo(), "cargo ")?;
+                let toolchain = version(
+                    cargo_toml.parent(),
+                    toolchain::cargo_with_env(&config.extra_env),
+                    "cargo ",
+                )?;
                 let meta = CargoWorkspace::fetch_metadata(
                     &cargo_toml,
                     cargo_toml.parent(),
@@ -345,8 +353,15 @@ impl ProjectWorkspace {
             tracing::info!(src_root = %sysroot.src_root().display(), root = %sysroot.root().display(), "Using sysroot");
         }
 
+        let validation_error = project_json.validate().err().map(|it| it.to_string());
         let rustc_cfg = rustc_cfg::get(None, target, extra_env);
-        ProjectWorkspace::Json { project: project_json, sysroot, rustc_cfg, toolchain }
+        ProjectWorkspace::Json {
+            project: project_json,
+            sysroot,
+            rustc_cfg,
+            toolchain,
+            validation_error,
+        }
     }
 
     pub fn load_detached_files(
@@ -373,7 +388,7 @@ impl ProjectWorkspace {
         if let Ok(sysroot) = &sysroot {
             tracing::info!(src_root = %sysroot.src_root().display(), root = %sysroot.root().display(), "Using sysroot");
         }
-        let rustc_cfg = rustc_cfg::get(None, None, &Default::default());
+        let rustc_cfg = rustc_cfg::get(None, config.target.as_deref(), &config.extra_env);
         Ok(ProjectWorkspace::DetachedFiles { files: detached_files, sysroot, rustc_cfg })
     }
 
@@ -488,7 +503,7 @@ impl ProjectWorkspace {
             })
         };
         match self {
-            ProjectWorkspace::Json { project, sysroot, rustc_cfg: _, toolchain: _ } => project
+            ProjectWorkspace::Json { project, sysroot, .. } => project
                 .crates()
                 .map(|(_, krate)| PackageRoot {
                     is_local: krate.is_workspace_member,
@@ -512,7 +527,7 @@ impl ProjectWorkspace {
                 cargo
                     .packages()
                     .map(|pkg| {
-                        let is_local = cargo[pkg].is_local;
+                        let is_local = cargo[pkg].origin.is_local();
                         let pkg_root = cargo[pkg].manifest.parent().to_path_buf();
 
                         let mut include = vec![pkg_root.clone()];
@@ -540,6 +555,9 @@ impl ProjectWorkspace {
                         let mut exclude = vec![pkg_root.join(".git")];
                         if is_local {
                             exclude.push(pkg_root.join("target"));
+                            // Build script outputs are included explicitly above, so they
+                            // stay loadable even though they live in the target directory.
+                            exclude.push(cargo.target_directory().to_path_buf());
                         } else {
                             exclude.push(pkg_root.join("tests"));
                             exclude.push(pkg_root.join("examples"));
@@ -595,7 +613,7 @@ impl ProjectWorkspace {
         let _p = profile::span("ProjectWorkspace::to_crate_graph");
 
         let (mut crate_graph, proc_macros) = match self {
-            ProjectWorkspace::Json { project, sysroot, rustc_cfg, toolchain } => {
+            ProjectWorkspace::Json { project, sysroot, rustc_cfg, toolchain, .. } => {
                 project_json_to_crate_graph(
                     rustc_cfg.clone(),
                     load,
@@ -679,18 +697,20 @@ impl ProjectWorkspace {
                     && sysroot == o_sysroot
             }
             (
-                Self::Json { project, sysroot, rustc_cfg, toolchain },
+                Self::Json { project, sysroot, rustc_cfg, toolchain, validation_error },
                 Self::Json {
                     project: o_project,
                     sysroot: o_sysroot,
                     rustc_cfg: o_rustc_cfg,
                     toolchain: o_toolchain,
+                    validation_error: o_validation_error,
                 },
             ) => {
                 project == o_project
                     && rustc_cfg == o_rustc_cfg
                     && sysroot == o_sysroot
                     && toolchain == o_toolchain
+                    && validation_error == o_validation_error
             }
             (
                 Self::DetachedFiles { files, sysroot, rustc_cfg },
@@ -862,7 +882,7 @@ fn cargo_to_crate_graph(
             let mut cfg_options = cfg_options.clone();
 
             // Add test cfg for local crates
-            if cargo[pkg].is_local {
+            if cargo[pkg].origin.is_local() {
                 cfg_options.insert_atom("test".into());
             }
 
@@ -903,8 +923,7 @@ fn cargo_to_crate_graph(
                 build_scripts.get_output(pkg),
                 cfg_options.clone(),
                 file_id,
-                &cargo[tgt].name,
-                cargo[tgt].is_proc_macro,
+                &cargo[tgt],
                 target_layout.clone(),
                 false,
                 channel,
@@ -944,6 +963,7 @@ fn cargo_to_crate_graph(
 
     // Now add a dep edge from all targets of upstream to the lib
     // target of downstream.
+    let mut delayed_dev_deps = vec![];
     for pkg in cargo.packages() {
         for dep in &cargo[pkg].dependencies {
             let Some(&to) = pkg_to_lib_crate.get(&dep.pkg) else { continue };
@@ -956,11 +976,22 @@ fn cargo_to_crate_graph(
                     continue;
                 }
 
+                // Dev-dependencies may form cycles with normal dependencies, so add
+                // them last to make sure it's the dev-dependency edge that gets dropped.
+                if dep.kind == DepKind::Dev {
+                    delayed_dev_deps.push((from, name.clone(), to));
+                    continue;
+                }
+
                 add_dep(crate_graph, from, name.clone(), to)
             }
         }
     }
 
+    for (from, name, to) in delayed_dev_deps {
+        add_dep(crate_graph, from, name, to);
+    }
+
     if has_private {
         // If the user provided a path to rustc sources, we add all the rustc_private crates
         // and create dependencies on them for the crates which opt-in to that
@@ -1119,8 +1150,7 @@ fn handle_rustc_crates(
                         build_scripts.get_output(pkg),
                         cfg_options.clone(),
                         file_id,
-                        &rustc_workspace[tgt].name,
-                        rustc_workspace[tgt].is_proc_macro,
+                        &rustc_workspace[tgt],
                         target_layout.clone(),
                         true,
                         channel,
@@ -1185,13 +1215,11 @@ fn add_target_crate_root(
     build_data: Option<&BuildScriptOutput>,
     cfg_options: CfgOptions,
     file_id: FileId,
-    cargo_name: &str,
-    is_proc_macro: bool,
+    tgt: &TargetData,
     target_layout: TargetLayoutLoadResult,
     rustc_crate: bool,
     channel: Option<ReleaseChannel>,
 ) -> CrateId {
-    let edition = pkg.edition;
     let potential_cfg_options = if pkg.features.is_empty() {
         None
     } else {
@@ -1223,16 +1251,16 @@ fn add_target_crate_root(
         }
     }
 
-    let display_name = CrateDisplayName::from_canonical_name(cargo_name.to_string());
+    let display_name = CrateDisplayName::from_canonical_name(tgt.name.clone());
     let crate_id = crate_graph.add_crate_root(
         file_id,
-        edition,
+        tgt.edition,
         Some(display_name),
         Some(pkg.version.to_string()),
         cfg_options,
         potential_cfg_options,
         env,
-        is_proc_macro,
+        tgt.is_proc_macro,
         if rustc_crate {
             CrateOrigin::Rustc { name: pkg.name.clone() }
         } else if pkg.is_member {
@@ -1243,9 +1271,9 @@ fn add_target_crate_root(
         target_layout,
         channel,
     );
-    if is_proc_macro {
+    if tgt.is_proc_macro {
         let proc_macro = match build_data.as_ref().map(|it| it.proc_macro_dylib_path.as_ref()) {
-            Some(it) => it.cloned().map(|path| Ok((Some(cargo_name.to_owned()), path))),
+            Some(it) => it.cloned().map(|path| Ok((Some(tgt.name.clone()), path))),
             None => Some(Err("crate has not yet been build".to_owned())),
         };
         if let Some(proc_macro) = proc_macro {
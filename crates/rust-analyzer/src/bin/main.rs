@@ -10,7 +10,7 @@ mod rustc_wrapper;
 use std::{env, fs, path::Path, process};
 
 use lsp_server::Connection;
-use rust_analyzer::{cli::flags, config::Config, from_json, Result};
+use rust_analyzer::{cli::flags, config::Config, from_json, ExitWithoutShutdown, Result};
 use vfs::AbsPathBuf;
 
 #[cfg(all(feature = "mimalloc"))]
@@ -40,7 +40,9 @@ fn main() {
     if let Err(err) = try_main(flags) {
         tracing::error!("Unexpected error: {}", err);
         eprintln!("{err}");
-        process::exit(101);
+        // The spec asks for 1 when the client exits without a shutdown request.
+        let code = if err.is::<ExitWithoutShutdown>() { 1 } else { 101 };
+        process::exit(code);
     }
 }
 
@@ -216,9 +218,16 @@ fn run_server() -> Result<()> {
         config.rediscover_workspaces();
     }
 
-    rust_analyzer::main_loop(config, connection)?;
-
-    io_threads.join()?;
+    match rust_analyzer::main_loop(config, connection) {
+        Ok(()) => io_threads.join()?,
+        Err(err) if err.is::<ExitWithoutShutdown>() => {
+            // The client sent `exit` or closed stdin anyway, so the IO threads
+            // are done as well.
+            io_threads.join()?;
+            return Err(err);
+        }
+        Err(err) => return Err(err),
+    }
     tracing::info!("server did shut down");
     Ok(())
 }
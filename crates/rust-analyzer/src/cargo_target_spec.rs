@@ -5,6 +5,7 @@ use std::mem;
 use cfg::{CfgAtom, CfgExpr};
 use ide::{Cancellable, FileId, RunnableKind, TestId};
 use project_model::{self, CargoFeatures, ManifestPath, TargetKind};
+use rustc_hash::FxHashSet;
 use vfs::AbsPathBuf;
 
 use crate::global_state::GlobalStateSnapshot;
@@ -100,7 +101,10 @@ impl CargoTargetSpec {
                 feats.extend(features.iter().cloned());
                 feats.extend(target_required_features);
 
-                feats.dedup();
+                // `Vec::dedup` only removes consecutive duplicates, but a required feature
+                // may well be enabled by the configuration already.
+                let mut seen = FxHashSet::default();
+                feats.retain(|feature| seen.insert(feature.clone()));
                 for feature in feats {
                     args.push("--features".to_owned());
                     args.push(feature);
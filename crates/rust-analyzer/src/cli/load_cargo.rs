@@ -114,6 +114,7 @@ pub fn load_workspace(
         load: project_folders.load,
         watch: vec![],
         version: 0,
+        exclude_gitignored: false,
     });
 
     tracing::debug!("crate graph: {:?}", crate_graph);
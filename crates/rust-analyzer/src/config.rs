@@ -13,14 +13,14 @@ use flycheck::FlycheckConfig;
 use ide::{
     AssistConfig, CallableSnippets, CompletionConfig, DiagnosticsConfig, ExprFillDefaultMode,
     HighlightConfig, HighlightRelatedConfig, HoverConfig, HoverDocFormat, InlayHintsConfig,
-    JoinLinesConfig, Snippet, SnippetScope,
+    JoinLinesConfig, Snippet, SnippetScope, TypingConfig,
 };
 use ide_db::{
     imports::insert_use::{ImportGranularity, InsertUseConfig, PrefixKind},
     SnippetCap,
 };
 use itertools::Itertools;
-use lsp_types::{ClientCapabilities, MarkupKind};
+use lsp_types::{ClientCapabilities, MarkupKind, ResourceOperationKind};
 use project_model::{
     CargoConfig, CargoFeatures, ProjectJson, ProjectJsonData, ProjectManifest, RustLibSource,
     UnsetTestCrates,
@@ -105,6 +105,7 @@ config_data! {
         cargo_extraArgs: Vec<String> = "[]",
         /// Extra environment variables that will be set when running cargo, rustc
         /// or other commands within the workspace. Useful for setting RUSTFLAGS.
+        /// Setting `CARGO` here also changes the cargo binary that is invoked.
         cargo_extraEnv: FxHashMap<String, String> = "{}",
         /// List of features to activate.
         ///
@@ -193,6 +194,10 @@ config_data! {
         ///
         /// Aliased as `"checkOnSave.targets"`.
         check_targets | checkOnSave_targets | checkOnSave_target: Option<CheckOnSaveTargets> = "null",
+        /// Whether `--workspace` should be passed to `cargo check`.
+        /// If false, `-p <package>` will be passed instead, checking only the
+        /// package of the saved file.
+        check_workspace: bool = "true",
 
         /// Toggles the additional completions that automatically add imports when completed.
         /// Note that your client must specify the `additionalTextEdits` LSP client capability to truly have this feature enabled.
@@ -276,6 +281,8 @@ config_data! {
         /// relative to the workspace root, and globs are not supported. You may
         /// also need to add the folders to Code's `files.watcherExclude`.
         files_excludeDirs: Vec<PathBuf> = "[]",
+        /// Whether to skip directories ignored by `.gitignore` files when scanning source roots.
+        files_excludeGitignored: bool = "false",
         /// Controls file watching implementation.
         files_watcher: FilesWatcherDef = "\"client\"",
 
@@ -513,6 +520,10 @@ config_data! {
 
         /// Whether to insert closing angle brackets when typing an opening angle bracket of a generic argument list.
         typing_autoClosingAngleBrackets_enable: bool = "false",
+        /// Whether to indent the line when typing `.` at its start inside a method call chain.
+        typing_chainIndent_enable: bool = "true",
+        /// Whether to insert or remove a trailing semicolon when typing `=` in a `let` statement or an assignment.
+        typing_semicolonOnEq_enable: bool = "true",
 
         /// Workspace symbol search kind.
         workspace_symbol_search_kind: WorkspaceSymbolSearchKindDef = "\"only_types\"",
@@ -661,6 +672,7 @@ impl HoverActionsConfig {
 pub struct FilesConfig {
     pub watcher: FilesWatcher,
     pub exclude: Vec<AbsPathBuf>,
+    pub exclude_gitignored: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -971,6 +983,23 @@ impl Config {
         .is_some()
     }
 
+    /// Whether the client can create and delete files, but not rename them, so
+    /// that moves have to be sent as a delete and a create.
+    pub fn emulate_file_renames(&self) -> bool {
+        let ops = try_or_def!(self
+            .caps
+            .workspace
+            .as_ref()?
+            .workspace_edit
+            .as_ref()?
+            .resource_operations
+            .as_deref()?);
+        let supports = |kind| ops.contains(&kind);
+        !supports(ResourceOperationKind::Rename)
+            && supports(ResourceOperationKind::Create)
+            && supports(ResourceOperationKind::Delete)
+    }
+
     pub fn code_action_resolve(&self) -> bool {
         try_or_def!(self
             .caps
@@ -1129,6 +1158,7 @@ impl Config {
                 _ => FilesWatcher::Server,
             },
             exclude: self.data.files_excludeDirs.iter().map(|it| self.root_path.join(it)).collect(),
+            exclude_gitignored: self.data.files_excludeGitignored,
         }
     }
 
@@ -1269,6 +1299,10 @@ impl Config {
         self.data.checkOnSave
     }
 
+    pub fn flycheck_workspace(&self) -> bool {
+        self.data.check_workspace
+    }
+
     pub fn runnables(&self) -> RunnablesConfig {
         RunnablesConfig {
             override_cargo: self.data.runnables_command.clone(),
@@ -1561,8 +1595,12 @@ impl Config {
         self.data.numThreads.unwrap_or(num_cpus::get_physical().try_into().unwrap_or(1))
     }
 
-    pub fn typing_autoclose_angle(&self) -> bool {
-        self.data.typing_autoClosingAngleBrackets_enable
+    pub fn typing(&self) -> TypingConfig {
+        TypingConfig {
+            autoclose_angle: self.data.typing_autoClosingAngleBrackets_enable,
+            chain_indent: self.data.typing_chainIndent_enable,
+            semicolon_on_eq: self.data.typing_semicolonOnEq_enable,
+        }
     }
 }
 // Deserialization definitions
@@ -2394,6 +2432,26 @@ mod tests {
         ensure_file_contents(&docs_path, &expected);
     }
 
+    #[test]
+    fn typing_rules_can_be_disabled() {
+        let root = AbsPathBuf::try_from(project_root()).unwrap();
+        let mut config = Config::new(root, ClientCapabilities::default(), Vec::new());
+        let typing = config.typing();
+        assert!(typing.chain_indent && typing.semicolon_on_eq);
+
+        config
+            .update(serde_json::json!({ "typing": { "chainIndent": { "enable": false } } }))
+            .unwrap();
+        let typing = config.typing();
+        assert!(!typing.chain_indent && typing.semicolon_on_eq);
+
+        config
+            .update(serde_json::json!({ "typing": { "semicolonOnEq": { "enable": false } } }))
+            .unwrap();
+        let typing = config.typing();
+        assert!(typing.chain_indent && !typing.semicolon_on_eq);
+    }
+
     fn remove_ws(text: &str) -> String {
         text.replace(char::is_whitespace, "")
     }
@@ -19,7 +19,9 @@ use crate::{
 
 pub(crate) fn abs_path(url: &lsp_types::Url) -> Result<AbsPathBuf> {
     let path = url.to_file_path().map_err(|()| "url is not a file")?;
-    Ok(AbsPathBuf::try_from(path).unwrap())
+    let path = AbsPathBuf::try_from(path)
+        .map_err(|path| format_err!("url is not absolute: {}", path.display()))?;
+    Ok(path.normalize())
 }
 
 pub(crate) fn vfs_path(url: &lsp_types::Url) -> Result<vfs::VfsPath> {
@@ -28,6 +28,7 @@ use crate::{
     op_queue::OpQueue,
     reload::{self, SourceRootConfig},
     task_pool::TaskPool,
+    timings::Timings,
     to_proto::url_from_abs_path,
     Result,
 };
@@ -58,6 +59,8 @@ pub(crate) struct GlobalState {
     pub(crate) diagnostics: DiagnosticCollection,
     pub(crate) mem_docs: MemDocs,
     pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
+    /// Wall-clock timings of handled requests and background jobs.
+    pub(crate) timings: Arc<Mutex<Timings>>,
     pub(crate) shutdown_requested: bool,
     pub(crate) last_reported_status: Option<lsp_ext::ServerStatusParams>,
     pub(crate) source_root_config: SourceRootConfig,
@@ -71,6 +74,10 @@ pub(crate) struct GlobalState {
 
     pub(crate) vfs: Arc<RwLock<(vfs::Vfs, NoHashHashMap<FileId, LineEndings>)>>,
     pub(crate) vfs_config_version: u32,
+    /// The `load`, `watch` and `exclude_gitignored` settings last sent to the VFS loader.
+    pub(crate) vfs_config: Option<(Vec<vfs::loader::Entry>, Vec<usize>, bool)>,
+    /// The glob patterns last registered with the client's file watcher.
+    pub(crate) watched_globs: Option<Vec<String>>,
     pub(crate) vfs_progress_config_version: u32,
     pub(crate) vfs_progress_n_total: usize,
     pub(crate) vfs_progress_n_done: usize,
@@ -102,6 +109,8 @@ pub(crate) struct GlobalState {
     /// to invalidate any salsa caches.
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
     pub(crate) fetch_workspaces_queue: OpQueue<(), Option<Vec<anyhow::Result<ProjectWorkspace>>>>,
+    /// The errors of the last workspace fetch, formatted once when it finished.
+    pub(crate) workspace_error: Option<Arc<str>>,
     pub(crate) fetch_build_data_queue:
         OpQueue<(), (Arc<Vec<ProjectWorkspace>>, Vec<anyhow::Result<WorkspaceBuildScripts>>)>,
     pub(crate) fetch_proc_macros_queue: OpQueue<Vec<ProcMacroPaths>, bool>,
@@ -116,8 +125,11 @@ pub(crate) struct GlobalStateSnapshot {
     pub(crate) check_fixes: CheckFixes,
     mem_docs: MemDocs,
     pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
+    pub(crate) timings: Arc<Mutex<Timings>>,
     vfs: Arc<RwLock<(vfs::Vfs, NoHashHashMap<FileId, LineEndings>)>>,
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
+    /// The errors of the last workspace fetch, if any.
+    pub(crate) workspace_error: Option<Arc<str>>,
     // used to signal semantic highlighting to fall back to syntax based highlighting until proc-macros have been loaded
     pub(crate) proc_macros_loaded: bool,
     pub(crate) flycheck: Arc<[FlycheckHandle]>,
@@ -156,6 +168,7 @@ impl GlobalState {
             diagnostics: Default::default(),
             mem_docs: MemDocs::default(),
             semantic_tokens_cache: Arc::new(Default::default()),
+            timings: Arc::new(Default::default()),
             shutdown_requested: false,
             last_reported_status: None,
             source_root_config: SourceRootConfig::default(),
@@ -169,12 +182,15 @@ impl GlobalState {
 
             vfs: Arc::new(RwLock::new((vfs::Vfs::default(), NoHashHashMap::default()))),
             vfs_config_version: 0,
+            vfs_config: None,
+            watched_globs: None,
             vfs_progress_config_version: 0,
             vfs_progress_n_total: 0,
             vfs_progress_n_done: 0,
 
             workspaces: Arc::new(Vec::new()),
             fetch_workspaces_queue: OpQueue::default(),
+            workspace_error: None,
             fetch_build_data_queue: OpQueue::default(),
             fetch_proc_macros_queue: OpQueue::default(),
 
@@ -310,11 +326,13 @@ impl GlobalState {
         GlobalStateSnapshot {
             config: Arc::clone(&self.config),
             workspaces: Arc::clone(&self.workspaces),
+            workspace_error: self.workspace_error.clone(),
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
             check_fixes: Arc::clone(&self.diagnostics.check_fixes),
             mem_docs: self.mem_docs.clone(),
             semantic_tokens_cache: Arc::clone(&self.semantic_tokens_cache),
+            timings: Arc::clone(&self.timings),
             proc_macros_loaded: !self.config.expand_proc_macros()
                 || *self.fetch_proc_macros_queue.last_op_result(),
             flycheck: self.flycheck.clone(),
@@ -367,6 +385,7 @@ impl GlobalState {
 
             let duration = start.elapsed();
             tracing::debug!("handled {} - ({}) in {:0.2?}", method, response.id, duration);
+            self.timings.lock().record(&method, duration);
             self.send(response.into());
         }
     }
@@ -425,6 +444,17 @@ impl GlobalStateSnapshot {
         self.vfs.read().0.file_path(file_id)
     }
 
+    /// Returns the files inside of `dir`, along with their path relative to it.
+    pub(crate) fn files_in_dir(&self, dir: &vfs::VfsPath) -> Vec<(FileId, String)> {
+        let vfs = &self.vfs.read().0;
+        vfs.iter()
+            .filter_map(|(file_id, path)| {
+                let rel_path = path.strip_prefix(dir)?.as_ref().to_str()?;
+                Some((file_id, rel_path.to_owned()))
+            })
+            .collect()
+    }
+
     pub(crate) fn cargo_target_for_crate_root(
         &self,
         crate_id: CrateId,
@@ -3,7 +3,7 @@
 //! `ide` crate.
 
 use std::{
-    io::Write as _,
+    io::{self, Write as _},
     process::{self, Stdio},
     sync::Arc,
 };
@@ -30,7 +30,7 @@ use project_model::{ManifestPath, ProjectWorkspace, TargetKind};
 use serde_json::json;
 use stdx::{format_to, never};
 use syntax::{algo, ast, AstNode, TextRange, TextSize};
-use vfs::{AbsPath, AbsPathBuf};
+use vfs::AbsPath;
 
 use crate::{
     cargo_target_spec::CargoTargetSpec,
@@ -96,13 +96,30 @@ pub(crate) fn handle_analyzer_status(
 
         format_to!(
             buf,
-            "Workspace root folders: {:?}",
+            "Workspace root folders: {:?}\n",
             snap.workspaces
                 .iter()
                 .flat_map(|ws| ws.workspace_definition_path())
                 .collect::<Vec<&AbsPath>>()
         );
+        for ws in snap.workspaces.iter() {
+            if let ProjectWorkspace::Cargo { cargo, .. } = ws {
+                format_to!(
+                    buf,
+                    "{}: {} packages ({} members), {} targets\n",
+                    cargo.workspace_root().display(),
+                    cargo.packages().len(),
+                    cargo.members().count(),
+                    cargo.packages().map(|pkg| cargo[pkg].targets.len()).sum::<usize>(),
+                );
+            }
+        }
     }
+    if let Some(error) = &snap.workspace_error {
+        format_to!(buf, "\nFailed to load workspaces:\n{}\n", error.trim_end());
+    }
+    buf.push_str("\nTimings:\n");
+    format_to!(buf, "{}", snap.timings.lock());
     buf.push_str("\nAnalysis:\n");
     buf.push_str(
         &snap
@@ -334,8 +351,7 @@ pub(crate) fn handle_on_type_formatting(
         return Ok(None);
     }
 
-    let edit =
-        snap.analysis.on_char_typed(position, char_typed, snap.config.typing_autoclose_angle())?;
+    let edit = snap.analysis.on_char_typed(position, char_typed, &snap.config.typing())?;
     let edit = match edit {
         Some(it) => it,
         None => return Ok(None),
@@ -653,13 +669,9 @@ pub(crate) fn handle_parent_module(
     params: lsp_types::TextDocumentPositionParams,
 ) -> Result<Option<lsp_types::GotoDefinitionResponse>> {
     let _p = profile::span("handle_parent_module");
-    if let Ok(file_path) = &params.text_document.uri.to_file_path() {
-        if file_path.file_name().unwrap_or_default() == "Cargo.toml" {
+    if let Ok(abs_path_buf) = from_proto::abs_path(&params.text_document.uri) {
+        if abs_path_buf.file_name().unwrap_or_default() == "Cargo.toml" {
             // search workspaces for parent packages or fallback to workspace root
-            let abs_path_buf = match AbsPathBuf::try_from(file_path.to_path_buf()).ok() {
-                Some(abs_path_buf) => abs_path_buf,
-                None => return Ok(None),
-            };
 
             let manifest_path = match ManifestPath::try_from(abs_path_buf).ok() {
                 Some(manifest_path) => manifest_path,
@@ -1766,6 +1778,8 @@ fn should_skip_target(runnable: &Runnable, cargo_spec: Option<&CargoTargetSpec>)
     }
 }
 
+const RUSTFMT_HINT: &str = "install it with `rustup component add rustfmt`";
+
 fn run_rustfmt(
     snap: &GlobalStateSnapshot,
     text_document: TextDocumentIdentifier,
@@ -1786,7 +1800,9 @@ fn run_rustfmt(
 
     let line_index = snap.file_line_index(file_id)?;
 
-    let mut command = match snap.config.rustfmt() {
+    let rustfmt_config = snap.config.rustfmt();
+    let is_rustfmt = matches!(rustfmt_config, RustfmtConfig::Rustfmt { .. });
+    let mut command = match rustfmt_config {
         RustfmtConfig::Rustfmt { extra_args, enable_range_formatting } => {
             let mut cmd = process::Command::new(toolchain::rustfmt());
             cmd.envs(snap.config.extra_env());
@@ -1851,12 +1867,16 @@ fn run_rustfmt(
         }
     };
 
-    let mut rustfmt = command
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context(format!("Failed to spawn {command:?}"))?;
+    let spawned =
+        command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+    let mut rustfmt = match spawned {
+        Err(e) if is_rustfmt && e.kind() == io::ErrorKind::NotFound => {
+            return Err(
+                LspError::new(-32900, format!("rustfmt was not found, {RUSTFMT_HINT}")).into()
+            )
+        }
+        it => it.context(format!("Failed to spawn {command:?}"))?,
+    };
 
     rustfmt.stdin.as_mut().unwrap().write_all(file.as_bytes())?;
 
@@ -1882,8 +1902,13 @@ fn run_rustfmt(
                 );
                 Ok(None)
             }
+            _ if is_rustfmt && rustfmt_not_installed => Err(LspError::new(
+                -32900,
+                format!("rustfmt is not installed, {RUSTFMT_HINT}:\n{captured_stderr}"),
+            )
+            .into()),
             _ => {
-                // Something else happened - e.g. `rustfmt` is missing or caught a signal
+                // Something else happened - e.g. `rustfmt` caught a signal
                 Err(LspError::new(
                     -32900,
                     format!(
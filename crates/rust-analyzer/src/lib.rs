@@ -35,6 +35,7 @@ mod op_queue;
 mod reload;
 mod semantic_tokens;
 mod task_pool;
+mod timings;
 mod to_proto;
 mod version;
 
@@ -48,7 +49,11 @@ use std::fmt;
 
 use serde::de::DeserializeOwned;
 
-pub use crate::{caps::server_capabilities, main_loop::main_loop, version::version};
+pub use crate::{
+    caps::server_capabilities,
+    main_loop::{main_loop, ExitWithoutShutdown},
+    version::version,
+};
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -14,9 +14,10 @@ use ide_db::base_db::{SourceDatabaseExt, VfsPath};
 use itertools::Itertools;
 use lsp_server::{Connection, Notification, Request};
 use lsp_types::notification::Notification as _;
-use vfs::{AbsPathBuf, ChangeKind, FileId};
+use vfs::{ChangeKind, FileId};
 
 use crate::{
+    cargo_target_spec::CargoTargetSpec,
     config::Config,
     dispatch::{NotificationDispatcher, RequestDispatcher},
     from_proto,
@@ -53,6 +54,19 @@ pub fn main_loop(config: Config, connection: Connection) -> Result<()> {
     GlobalState::new(connection.sender, config).run(connection.receiver)
 }
 
+/// The client ended the session without requesting a shutdown first, either by
+/// sending `exit` right away or by closing the connection.
+#[derive(Debug)]
+pub struct ExitWithoutShutdown;
+
+impl fmt::Display for ExitWithoutShutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("client exited without proper shutdown sequence")
+    }
+}
+
+impl std::error::Error for ExitWithoutShutdown {}
+
 enum Event {
     Lsp(lsp_server::Message),
     Task(Task),
@@ -134,6 +148,11 @@ impl GlobalState {
                             scheme: None,
                             pattern: Some("**/Cargo.lock".into()),
                         },
+                        lsp_types::DocumentFilter {
+                            language: None,
+                            scheme: None,
+                            pattern: Some("**/rust-project.json".into()),
+                        },
                     ]),
                 },
             };
@@ -154,16 +173,29 @@ impl GlobalState {
             self.fetch_workspaces(cause);
         }
 
-        while let Some(event) = self.next_event(&inbox) {
+        let res = self.handle_events(&inbox);
+        // Don't leave a hanging `cargo metadata` or build script behind.
+        if let Err(unfinished) = self.task_pool.handle.shutdown(Duration::from_secs(1)) {
+            tracing::warn!("killed the child processes of unfinished tasks: {unfinished:?}");
+        }
+        res
+    }
+
+    fn handle_events(&mut self, inbox: &Receiver<lsp_server::Message>) -> Result<()> {
+        while let Some(event) = self.next_event(inbox) {
             if let Event::Lsp(lsp_server::Message::Notification(not)) = &event {
                 if not.method == lsp_types::notification::Exit::METHOD {
+                    // Per the spec, `exit` without a prior `shutdown` is an error.
+                    if !self.shutdown_requested {
+                        break;
+                    }
                     return Ok(());
                 }
             }
             self.handle_event(event)?
         }
 
-        Err("client exited without proper shutdown sequence".into())
+        Err(ExitWithoutShutdown.into())
     }
 
     fn next_event(&self, inbox: &Receiver<lsp_server::Message>) -> Option<Event> {
@@ -412,8 +444,7 @@ impl GlobalState {
                 self.send_notification::<lsp_ext::ServerStatusNotification>(status);
             } else if let (health, Some(message)) = (status.health, &status.message) {
                 let open_log_button = tracing::enabled!(tracing::Level::ERROR)
-                    && (self.fetch_build_data_error().is_err()
-                        || self.fetch_workspace_error().is_err());
+                    && (self.fetch_build_data_error().is_err() || self.workspace_error.is_some());
                 self.show_message(
                     match health {
                         lsp_ext::Health::Ok => lsp_types::MessageType::INFO,
@@ -457,9 +488,11 @@ impl GlobalState {
                     ProjectWorkspaceProgress::Report(msg) => (Progress::Report, Some(msg)),
                     ProjectWorkspaceProgress::End(workspaces) => {
                         self.fetch_workspaces_queue.op_completed(Some(workspaces));
-                        if let Err(e) = self.fetch_workspace_error() {
+                        let error = self.fetch_workspace_error().err().map(Arc::<str>::from);
+                        if let Some(e) = &error {
                             tracing::error!("FetchWorkspaceError:\n{e}");
                         }
+                        self.workspace_error = error.clone();
 
                         let old = Arc::clone(&self.workspaces);
                         self.switch_workspaces("fetched workspace".to_string());
@@ -470,7 +503,8 @@ impl GlobalState {
                                 .request_op(format!("workspace updated"), ());
                         }
 
-                        (Progress::End, None)
+                        // Don't let the progress end silently when loading failed.
+                        (Progress::End, error.map(|_| "failed to load workspaces".to_owned()))
                     }
                 };
 
@@ -520,7 +554,20 @@ impl GlobalState {
             vfs::loader::Message::Loaded { files } => {
                 let vfs = &mut self.vfs.write().0;
                 for (path, contents) in files {
+                    // Such files could never be referred to by the client, and would make any
+                    // response mentioning them fail to serialize. Paths known to the VFS
+                    // already passed this check when they were first loaded.
                     let path = VfsPath::from(path);
+                    if vfs.file_id(&path).is_none()
+                        && path
+                            .as_path()
+                            .map_or(false, |it| lsp_types::Url::from_file_path(it).is_err())
+                    {
+                        tracing::warn!(
+                            "skipping file with a path not representable as a url: {path}"
+                        );
+                        continue;
+                    }
                     if !self.mem_docs.contains(&path) {
                         vfs.set_file_contents(path, contents);
                     }
@@ -759,6 +806,15 @@ impl GlobalState {
                     let crate_root_paths: Vec<_> =
                         crate_root_paths.iter().map(Deref::deref).collect();
 
+                    // With `check.workspace` disabled, only the package owning the saved
+                    // file is checked, in the workspace it belongs to. If that package
+                    // can't be determined, the whole workspace is checked instead.
+                    let package = if world.config.flycheck_workspace() {
+                        None
+                    } else {
+                        CargoTargetSpec::for_file(&world, file_id).ok().flatten()
+                    };
+
                     // Find all workspaces that have at least one target containing the saved file
                     let workspace_ids =
                         world.workspaces.iter().enumerate().filter(|(_, ws)| match ws {
@@ -777,10 +833,18 @@ impl GlobalState {
 
                     // Find and trigger corresponding flychecks
                     for flycheck in world.flycheck.iter() {
-                        for (id, _) in workspace_ids.clone() {
+                        for (id, ws) in workspace_ids.clone() {
                             if id == flycheck.id() {
                                 updated = true;
-                                flycheck.restart();
+                                match (&package, ws) {
+                                    (
+                                        Some(spec),
+                                        project_model::ProjectWorkspace::Cargo { cargo, .. },
+                                    ) if cargo.workspace_root() == &*spec.workspace_root => {
+                                        flycheck.restart_for_package(spec.package.clone())
+                                    }
+                                    _ => flycheck.restart(),
+                                }
                                 continue;
                             }
                         }
@@ -970,8 +1034,7 @@ impl GlobalState {
                 let config = Arc::make_mut(&mut this.config);
 
                 for workspace in params.event.removed {
-                    let Ok(path) = workspace.uri.to_file_path() else { continue };
-                    let Ok(path) = AbsPathBuf::try_from(path) else { continue };
+                    let Ok(path) = from_proto::abs_path(&workspace.uri) else { continue };
                     let Some(position) = config.workspace_roots.iter().position(|it| it == &path) else { continue };
                     config.workspace_roots.remove(position);
                 }
@@ -980,8 +1043,7 @@ impl GlobalState {
                     .event
                     .added
                     .into_iter()
-                    .filter_map(|it| it.uri.to_file_path().ok())
-                    .filter_map(|it| AbsPathBuf::try_from(it).ok());
+                    .filter_map(|it| from_proto::abs_path(&it.uri).ok());
                 config.workspace_roots.extend(added);
                     if !config.has_linked_projects() && config.detached_files().is_empty() {
                         config.rediscover_workspaces();
@@ -991,10 +1053,16 @@ impl GlobalState {
                 Ok(())
             })?
             .on::<lsp_types::notification::DidChangeWatchedFiles>(|this, params| {
-                for change in params.changes {
-                    if let Ok(path) = from_proto::abs_path(&change.uri) {
-                        this.loader.handle.invalidate(path);
-                    }
+                // Overlapping watchers may report the same file more than once.
+                let mut paths: Vec<_> = params
+                    .changes
+                    .iter()
+                    .filter_map(|change| from_proto::abs_path(&change.uri).ok())
+                    .collect();
+                paths.sort();
+                paths.dedup();
+                for path in paths {
+                    this.loader.handle.invalidate(path);
                 }
                 Ok(())
             })?
@@ -42,3 +42,34 @@ impl<Args, Output> OpQueue<Args, Output> {
         self.op_requested.is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::OpQueue;
+
+    #[test]
+    fn coalesces_pending_requests() {
+        let mut queue = OpQueue::<u32, u32>::default();
+
+        for i in 0..10 {
+            queue.request_op(format!("request {i}"), i);
+        }
+        let (_cause, arg) = queue.should_start_op().unwrap();
+        assert_eq!(arg, 9);
+
+        // Requests arriving while the op is running are coalesced into a single
+        // follow-up op with the most recent arguments.
+        for i in 10..20 {
+            queue.request_op(format!("request {i}"), i);
+            assert!(queue.should_start_op().is_none());
+        }
+        queue.op_completed(arg);
+
+        let (_cause, arg) = queue.should_start_op().unwrap();
+        assert_eq!(arg, 19);
+        queue.op_completed(arg);
+
+        assert!(queue.should_start_op().is_none());
+        assert_eq!(*queue.last_op_result(), 19);
+    }
+}
@@ -12,7 +12,7 @@
 //! correct. Instead, we try to provide a best-effort service. Even if the
 //! project is currently loading and we don't have a full project model, we
 //! still want to respond to various  requests.
-use std::{collections::hash_map::Entry, iter, mem, sync::Arc};
+use std::{collections::hash_map::Entry, iter, mem, panic, sync::Arc, time::Instant};
 
 use flycheck::{FlycheckConfig, FlycheckHandle};
 use hir::db::DefDatabase;
@@ -26,7 +26,7 @@ use ide_db::{
 };
 use itertools::Itertools;
 use proc_macro_api::{MacroDylib, ProcMacroServer};
-use project_model::{PackageRoot, ProjectWorkspace, WorkspaceBuildScripts};
+use project_model::{PackageRoot, ProjectManifest, ProjectWorkspace, WorkspaceBuildScripts};
 use syntax::SmolStr;
 use vfs::{file_set::FileSetConfig, AbsPath, AbsPathBuf, ChangeKind};
 
@@ -84,7 +84,13 @@ impl GlobalState {
         }
         if self.config.linked_projects() != old_config.linked_projects() {
             self.fetch_workspaces_queue.request_op("linked projects changed".to_string(), ())
-        } else if self.config.flycheck() != old_config.flycheck() {
+        } else if self.config.cargo() != old_config.cargo() {
+            self.fetch_workspaces_queue.request_op("cargo config changed".to_string(), ())
+        }
+
+        // A workspace reload doesn't necessarily restart flycheck, e.g. when the
+        // workspaces turn out unchanged, so do that regardless.
+        if self.config.flycheck() != old_config.flycheck() {
             self.reload_flycheck();
         }
 
@@ -144,11 +150,19 @@ impl GlobalState {
                 message.push_str(e);
                 message.push_str("\n\n");
             }
+            if let ProjectWorkspace::Json { validation_error: Some(e), .. } = ws {
+                status.health = lsp_ext::Health::Warning;
+                message.push_str(e);
+                message.push_str("\n\n");
+            }
         }
 
-        if let Err(_) = self.fetch_workspace_error() {
+        if let Some(error) = &self.workspace_error {
             status.health = lsp_ext::Health::Error;
-            message.push_str("Failed to load workspaces.\n\n");
+            message.push_str("Failed to load workspaces.\n");
+            // Include cargo's error, it usually points at the offending manifest.
+            message.push_str(error.trim_end());
+            message.push_str("\n\n");
         }
 
         if !message.is_empty() {
@@ -160,43 +174,74 @@ impl GlobalState {
     pub(crate) fn fetch_workspaces(&mut self, cause: Cause) {
         tracing::info!(%cause, "will fetch workspaces");
 
-        self.task_pool.handle.spawn_with_sender({
+        // Don't block exiting on a `cargo metadata` that is still running.
+        self.task_pool.handle.spawn_detached_with_sender("FetchWorkspace", {
             let linked_projects = self.config.linked_projects();
             let detached_files = self.config.detached_files().to_vec();
             let cargo_config = self.config.cargo();
+            let timings = Arc::clone(&self.timings);
 
             move |sender| {
                 let progress = {
                     let sender = sender.clone();
                     move |msg| {
-                        sender
-                            .send(Task::FetchWorkspace(ProjectWorkspaceProgress::Report(msg)))
-                            .unwrap()
+                        // The main loop may have exited in the meantime.
+                        _ = sender.send(Task::FetchWorkspace(ProjectWorkspaceProgress::Report(msg)))
                     }
                 };
 
-                sender.send(Task::FetchWorkspace(ProjectWorkspaceProgress::Begin)).unwrap();
+                _ = sender.send(Task::FetchWorkspace(ProjectWorkspaceProgress::Begin));
 
-                let mut workspaces = linked_projects
-                    .iter()
-                    .map(|project| match project {
-                        LinkedProject::ProjectManifest(manifest) => {
-                            project_model::ProjectWorkspace::load(
-                                manifest.clone(),
-                                &cargo_config,
-                                &progress,
-                            )
-                        }
-                        LinkedProject::InlineJsonProject(it) => {
-                            Ok(project_model::ProjectWorkspace::load_inline(
-                                it.clone(),
-                                cargo_config.target.as_deref(),
-                                &cargo_config.extra_env,
-                                None,
-                            ))
-                        }
+                let load_project = |project: &LinkedProject| match project {
+                    LinkedProject::ProjectManifest(manifest) => {
+                        project_model::ProjectWorkspace::load(
+                            manifest.clone(),
+                            &cargo_config,
+                            &progress,
+                        )
+                    }
+                    LinkedProject::InlineJsonProject(it) => {
+                        Ok(project_model::ProjectWorkspace::load_inline(
+                            it.clone(),
+                            cargo_config.target.as_deref(),
+                            &cargo_config.extra_env,
+                            None,
+                        ))
+                    }
+                };
+                // A panic would otherwise kill the task before it reports back, leaving the
+                // main loop waiting for this fetch forever and ignoring all further reloads.
+                let load = |project: &LinkedProject| {
+                    let start = Instant::now();
+                    let res =
+                        panic::catch_unwind(panic::AssertUnwindSafe(|| load_project(project)))
+                            .unwrap_or_else(|panic| {
+                                let message = panic
+                                    .downcast_ref::<String>()
+                                    .map(String::as_str)
+                                    .or_else(|| panic.downcast_ref::<&str>().copied())
+                                    .unwrap_or("unknown panic");
+                                Err(anyhow::format_err!("workspace loading panicked: {message}"))
+                            });
+                    timings.lock().record("load workspace", start.elapsed());
+                    res
+                };
+                // Load the projects concurrently, so that one slow `cargo metadata`
+                // doesn't hold up all the others. The results keep the order of
+                // `linked_projects`.
+                let mut workspaces = if linked_projects.len() > 1 {
+                    std::thread::scope(|s| {
+                        let handles = linked_projects
+                            .iter()
+                            .map(|project| s.spawn(|| load(project)))
+                            .collect::<Vec<_>>();
+                        handles.into_iter().map(|it| it.join().unwrap()).collect::<Vec<_>>()
                     })
-                    .collect::<Vec<_>>();
+                } else {
+                    linked_projects.iter().map(load).collect::<Vec<_>>()
+                };
+
+                dedup_workspaces(&mut workspaces);
 
                 if !detached_files.is_empty() {
                     workspaces.push(project_model::ProjectWorkspace::load_detached_files(
@@ -206,9 +251,7 @@ impl GlobalState {
                 }
 
                 tracing::info!("did fetch workspaces {:?}", workspaces);
-                sender
-                    .send(Task::FetchWorkspace(ProjectWorkspaceProgress::End(workspaces)))
-                    .unwrap();
+                _ = sender.send(Task::FetchWorkspace(ProjectWorkspaceProgress::End(workspaces)));
             }
         });
     }
@@ -217,18 +260,23 @@ impl GlobalState {
         tracing::info!(%cause, "will fetch build data");
         let workspaces = Arc::clone(&self.workspaces);
         let config = self.config.cargo();
-        self.task_pool.handle.spawn_with_sender(move |sender| {
-            sender.send(Task::FetchBuildData(BuildDataProgress::Begin)).unwrap();
+        let timings = Arc::clone(&self.timings);
+        // Don't block exiting on build scripts that are still running.
+        self.task_pool.handle.spawn_detached_with_sender("FetchBuildData", move |sender| {
+            // The main loop may have exited in the meantime.
+            _ = sender.send(Task::FetchBuildData(BuildDataProgress::Begin));
+            let start = Instant::now();
 
             let progress = {
                 let sender = sender.clone();
                 move |msg| {
-                    sender.send(Task::FetchBuildData(BuildDataProgress::Report(msg))).unwrap()
+                    _ = sender.send(Task::FetchBuildData(BuildDataProgress::Report(msg)));
                 }
             };
             let res = ProjectWorkspace::run_all_build_scripts(&workspaces, &config, &progress);
+            timings.lock().record("run build scripts", start.elapsed());
 
-            sender.send(Task::FetchBuildData(BuildDataProgress::End((workspaces, res)))).unwrap();
+            _ = sender.send(Task::FetchBuildData(BuildDataProgress::End((workspaces, res))));
         });
     }
 
@@ -291,18 +339,24 @@ impl GlobalState {
         let _p = profile::span("GlobalState::switch_workspaces");
         tracing::info!(%cause, "will switch workspaces");
 
-        if let Err(_) = self.fetch_workspace_error() {
-            if !self.workspaces.is_empty() {
-                // It only makes sense to switch to a partially broken workspace
+        let Some(workspaces) = self.fetch_workspaces_queue.last_op_result() else { return; };
+        let mut workspaces =
+            workspaces.iter().filter_map(|res| res.as_ref().ok().cloned()).collect::<Vec<_>>();
+
+        if self.workspace_error.is_some() && !self.workspaces.is_empty() {
+            if workspaces.is_empty() {
+                // It only makes sense to switch to a completely broken workspace
                 // if we don't have any workspace at all yet.
                 return;
             }
+            // Some of the projects failed to load. Keep the previous version of
+            // those that are still linked, so that one broken project doesn't
+            // take down the others.
+            let retained =
+                retained_workspaces(&self.workspaces, &workspaces, &self.config.linked_projects());
+            workspaces.extend(retained);
         }
 
-        let Some(workspaces) = self.fetch_workspaces_queue.last_op_result() else { return; };
-        let workspaces =
-            workspaces.iter().filter_map(|res| res.as_ref().ok().cloned()).collect::<Vec<_>>();
-
         let same_workspaces = workspaces.len() == self.workspaces.len()
             && workspaces
                 .iter()
@@ -342,36 +396,59 @@ impl GlobalState {
         }
 
         if let FilesWatcher::Client = self.config.files().watcher {
-            let registration_options = lsp_types::DidChangeWatchedFilesRegistrationOptions {
-                watchers: self
-                    .workspaces
-                    .iter()
-                    .flat_map(|ws| ws.to_roots())
-                    .filter(|it| it.is_local)
-                    .flat_map(|root| {
-                        root.include.into_iter().flat_map(|it| {
-                            [
-                                format!("{}/**/*.rs", it.display()),
-                                format!("{}/**/Cargo.toml", it.display()),
-                                format!("{}/**/Cargo.lock", it.display()),
-                            ]
-                        })
-                    })
-                    .map(|glob_pattern| lsp_types::FileSystemWatcher {
-                        glob_pattern: lsp_types::GlobPattern::String(glob_pattern),
-                        kind: None,
+            // Nested roots produce overlapping patterns, only send each once.
+            let mut globs: Vec<String> = self
+                .workspaces
+                .iter()
+                .flat_map(|ws| ws.to_roots())
+                .filter(|it| it.is_local)
+                .flat_map(|root| {
+                    root.include.into_iter().flat_map(|it| {
+                        [
+                            format!("{}/**/*.rs", it.display()),
+                            format!("{}/**/Cargo.toml", it.display()),
+                            format!("{}/**/Cargo.lock", it.display()),
+                            format!("{}/**/rust-project.json", it.display()),
+                        ]
                     })
-                    .collect(),
-            };
-            let registration = lsp_types::Registration {
-                id: "workspace/didChangeWatchedFiles".to_string(),
-                method: "workspace/didChangeWatchedFiles".to_string(),
-                register_options: Some(serde_json::to_value(registration_options).unwrap()),
-            };
-            self.send_request::<lsp_types::request::RegisterCapability>(
-                lsp_types::RegistrationParams { registrations: vec![registration] },
-                |_, _| (),
-            );
+                })
+                .collect();
+            globs.sort();
+            globs.dedup();
+
+            if self.watched_globs.as_ref() != Some(&globs) {
+                const ID: &str = "workspace/didChangeWatchedFiles";
+                if self.watched_globs.is_some() {
+                    self.send_request::<lsp_types::request::UnregisterCapability>(
+                        lsp_types::UnregistrationParams {
+                            unregisterations: vec![lsp_types::Unregistration {
+                                id: ID.to_string(),
+                                method: ID.to_string(),
+                            }],
+                        },
+                        |_, _| (),
+                    );
+                }
+                let registration_options = lsp_types::DidChangeWatchedFilesRegistrationOptions {
+                    watchers: globs
+                        .iter()
+                        .map(|glob_pattern| lsp_types::FileSystemWatcher {
+                            glob_pattern: lsp_types::GlobPattern::String(glob_pattern.clone()),
+                            kind: None,
+                        })
+                        .collect(),
+                };
+                let registration = lsp_types::Registration {
+                    id: ID.to_string(),
+                    method: ID.to_string(),
+                    register_options: Some(serde_json::to_value(registration_options).unwrap()),
+                };
+                self.send_request::<lsp_types::request::RegisterCapability>(
+                    lsp_types::RegistrationParams { registrations: vec![registration] },
+                    |_, _| (),
+                );
+                self.watched_globs = Some(globs);
+            }
         }
 
         let files_config = self.config.files();
@@ -416,12 +493,20 @@ impl GlobalState {
             FilesWatcher::Client => vec![],
             FilesWatcher::Server => project_folders.watch,
         };
-        self.vfs_config_version += 1;
-        self.loader.handle.set_config(vfs::loader::Config {
-            load: project_folders.load,
-            watch,
-            version: self.vfs_config_version,
-        });
+        // Rescanning all the files is expensive, so skip it if the set of roots didn't
+        // change, like when only a dependency or the version of a package was edited.
+        let vfs_config = Some((project_folders.load, watch, files_config.exclude_gitignored));
+        if self.vfs_config != vfs_config {
+            self.vfs_config = vfs_config;
+            let (load, watch, exclude_gitignored) = self.vfs_config.clone().unwrap();
+            self.vfs_config_version += 1;
+            self.loader.handle.set_config(vfs::loader::Config {
+                load,
+                watch,
+                version: self.vfs_config_version,
+                exclude_gitignored,
+            });
+        }
         self.source_root_config = project_folders.source_root_config;
 
         // Create crate graph from all the workspaces
@@ -815,7 +900,7 @@ pub(crate) fn should_refresh_for_change(path: &AbsPath, change_kind: ChangeKind)
         None => return false,
     };
 
-    if let "Cargo.toml" | "Cargo.lock" = file_name {
+    if let "Cargo.toml" | "Cargo.lock" | "rust-project.json" = file_name {
         return true;
     }
     if change_kind == ChangeKind::Modify {
@@ -850,3 +935,190 @@ pub(crate) fn should_refresh_for_change(path: &AbsPath, change_kind: ChangeKind)
     }
     false
 }
+
+/// Returns the `old` workspaces which failed to reload, that is which are still
+/// linked but missing from the freshly loaded `new` ones.
+///
+/// A linked manifest need not be at the root of its workspace, e.g. for a cargo
+/// workspace linked through one of its members, so it is attributed to the
+/// innermost `old` workspace containing it.
+fn retained_workspaces(
+    old: &[ProjectWorkspace],
+    new: &[ProjectWorkspace],
+    linked_projects: &[LinkedProject],
+) -> Vec<ProjectWorkspace> {
+    let still_linked: Vec<usize> = linked_projects
+        .iter()
+        .filter_map(|project| {
+            let path: &AbsPath = match project {
+                LinkedProject::ProjectManifest(
+                    ProjectManifest::ProjectJson(manifest) | ProjectManifest::CargoToml(manifest),
+                ) => manifest,
+                LinkedProject::InlineJsonProject(project) => project.path(),
+            };
+            old.iter()
+                .enumerate()
+                .filter_map(|(idx, ws)| Some((idx, ws.workspace_definition_path()?)))
+                .filter(|(_, root)| path.starts_with(root))
+                .max_by_key(|(_, root)| root.as_ref().components().count())
+                .map(|(idx, _)| idx)
+        })
+        .collect();
+    old.iter()
+        .enumerate()
+        .filter(|(idx, old)| {
+            let Some(root) = old.workspace_definition_path() else { return false };
+            let reloaded = new.iter().any(|new| new.workspace_definition_path() == Some(root));
+            !reloaded && still_linked.contains(idx)
+        })
+        .map(|(_, old)| old.clone())
+        .collect()
+}
+
+/// Several linked projects may resolve to the same workspace, for example when
+/// the manifests of two members of one cargo workspace were configured. Loading
+/// it twice would duplicate every crate in the crate graph, so only the first
+/// one is kept.
+fn dedup_workspaces(workspaces: &mut Vec<anyhow::Result<ProjectWorkspace>>) {
+    let mut i = 0;
+    while i < workspaces.len() {
+        if let Ok(w) = &workspaces[i] {
+            let dupes: Vec<_> = workspaces[i + 1..]
+                .iter()
+                .positions(|it| matches!(it, Ok(ws) if ws.eq_ignore_build_data(w)))
+                .collect();
+            dupes.into_iter().rev().for_each(|d| {
+                _ = workspaces.remove(d + i + 1);
+            });
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use project_model::{ManifestPath, ProjectJson, ProjectManifest, ProjectWorkspace};
+    use vfs::{AbsPathBuf, ChangeKind};
+
+    use crate::config::LinkedProject;
+
+    use super::{dedup_workspaces, retained_workspaces, should_refresh_for_change};
+
+    #[track_caller]
+    fn check(path: &str, change_kind: ChangeKind, expected: bool) {
+        let root = if cfg!(windows) { "C:\\ws" } else { "/ws" };
+        let path = AbsPathBuf::assert(format!("{root}/{path}").into());
+        assert_eq!(should_refresh_for_change(&path, change_kind), expected, "{}", path.display());
+    }
+
+    #[test]
+    fn refresh_on_manifest_changes() {
+        check("Cargo.toml", ChangeKind::Modify, true);
+        check("member/Cargo.toml", ChangeKind::Create, true);
+        check("Cargo.lock", ChangeKind::Modify, true);
+        check("rust-project.json", ChangeKind::Modify, true);
+        check(".cargo/config.toml", ChangeKind::Create, true);
+        check("config.toml", ChangeKind::Create, false);
+    }
+
+    #[test]
+    fn refresh_on_implicit_targets() {
+        check("src/lib.rs", ChangeKind::Create, true);
+        check("src/bin/tool.rs", ChangeKind::Delete, true);
+        check("examples/demo/main.rs", ChangeKind::Create, true);
+        check("src/bin/tool.rs", ChangeKind::Modify, false);
+        check("src/foo.rs", ChangeKind::Create, false);
+    }
+
+    #[test]
+    fn dedup_linked_workspaces() {
+        let root = if cfg!(windows) { "C:\\ws" } else { "/ws" };
+        let detached = |file: &str| ProjectWorkspace::DetachedFiles {
+            files: vec![AbsPathBuf::assert(format!("{root}/{file}").into())],
+            sysroot: Err(None),
+            rustc_cfg: Vec::new(),
+        };
+        let mut workspaces = vec![
+            Ok(detached("a.rs")),
+            Err(anyhow::format_err!("failed to load")),
+            Ok(detached("b.rs")),
+            Ok(detached("a.rs")),
+            Err(anyhow::format_err!("failed to load")),
+            Ok(detached("b.rs")),
+        ];
+        dedup_workspaces(&mut workspaces);
+        let workspaces = workspaces
+            .iter()
+            .map(|it| match it {
+                Ok(ProjectWorkspace::DetachedFiles { files, .. }) => {
+                    files[0].file_name().unwrap().to_str().unwrap().to_owned()
+                }
+                Ok(_) => unreachable!(),
+                Err(e) => e.to_string(),
+            })
+            .collect::<Vec<_>>();
+        // Errors are never merged, they are reported separately.
+        assert_eq!(workspaces, ["a.rs", "failed to load", "b.rs", "failed to load"]);
+    }
+
+    fn json_workspace(dir: &str) -> ProjectWorkspace {
+        let root = if cfg!(windows) { "C:\\" } else { "/" };
+        let base = AbsPathBuf::assert(format!("{root}{dir}").into());
+        let data = serde_json::from_value(serde_json::json!({ "crates": [] })).unwrap();
+        ProjectWorkspace::Json {
+            project: ProjectJson::new(&base, data),
+            sysroot: Err(None),
+            rustc_cfg: Vec::new(),
+            toolchain: None,
+            validation_error: None,
+        }
+    }
+
+    fn linked(ws: &ProjectWorkspace) -> LinkedProject {
+        let manifest = ws.workspace_definition_path().unwrap().join("rust-project.json");
+        ProjectManifest::ProjectJson(ManifestPath::try_from(manifest).unwrap()).into()
+    }
+
+    fn retained(
+        old: &[ProjectWorkspace],
+        new: &[ProjectWorkspace],
+        linked_projects: &[LinkedProject],
+    ) -> Vec<String> {
+        retained_workspaces(old, new, linked_projects)
+            .iter()
+            .map(|it| it.workspace_definition_path().unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn retain_workspace_that_failed_to_reload() {
+        let (a, b) = (json_workspace("a"), json_workspace("b"));
+        let linked_projects = [linked(&a), linked(&b)];
+        // `b` failed to reload, so its previous version is kept.
+        let res = retained(&[a.clone(), b.clone()], &[a.clone()], &linked_projects);
+        assert_eq!(res, [b.workspace_definition_path().unwrap().display().to_string()]);
+        // Both reloaded, nothing to retain.
+        let res = retained(&[a.clone(), b.clone()], &[a.clone(), b.clone()], &linked_projects);
+        assert!(res.is_empty(), "{res:?}");
+    }
+
+    #[test]
+    fn drop_unlinked_workspace() {
+        let (a, b) = (json_workspace("a"), json_workspace("b"));
+        let nested = json_workspace("b/nested");
+        let res = retained(&[a.clone(), b.clone()], &[a.clone()], &[linked(&a)]);
+        assert!(res.is_empty(), "{res:?}");
+        // The manifest of `b/nested` belongs to the innermost workspace only.
+        let res = retained(&[a.clone(), b, nested.clone()], &[a.clone()], &[linked(&nested)]);
+        assert_eq!(res, [nested.workspace_definition_path().unwrap().display().to_string()]);
+    }
+
+    #[test]
+    fn retain_workspace_linked_through_member() {
+        let (a, ws) = (json_workspace("a"), json_workspace("ws"));
+        let member = ws.workspace_definition_path().unwrap().join("member/Cargo.toml");
+        let member = ProjectManifest::CargoToml(ManifestPath::try_from(member).unwrap()).into();
+        let res = retained(&[a.clone(), ws.clone()], &[a.clone()], &[linked(&a), member]);
+        assert_eq!(res, [ws.workspace_definition_path().unwrap().display().to_string()]);
+    }
+}
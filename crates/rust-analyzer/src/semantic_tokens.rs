@@ -61,6 +61,7 @@ define_semantic_token_types![
         (BUILTIN_ATTRIBUTE, "builtinAttribute"),
         (BUILTIN_TYPE, "builtinType"),
         (CHAR, "character"),
+        (CODE_SPAN, "codeSpan"),
         (COLON, "colon"),
         (COMMA, "comma"),
         (COMPARISON, "comparison"),
@@ -71,6 +72,7 @@ define_semantic_token_types![
         (ESCAPE_SEQUENCE, "escapeSequence"),
         (FORMAT_SPECIFIER, "formatSpecifier"),
         (GENERIC, "generic"),
+        (INVALID_ESCAPE_SEQUENCE, "invalidEscapeSequence"),
         (LABEL, "label"),
         (LIFETIME, "lifetime"),
         (LOGICAL, "logical"),
@@ -80,6 +82,7 @@ define_semantic_token_types![
         (SELF_KEYWORD, "selfKeyword"),
         (SELF_TYPE_KEYWORD, "selfTypeKeyword"),
         (SEMICOLON, "semicolon"),
+        (TASK_MARKER, "taskMarker"),
         (TYPE_ALIAS, "typeAlias"),
         (TOOL_MODULE, "toolModule"),
         (UNION, "union"),
@@ -1,22 +1,47 @@
 //! A thin wrapper around `ThreadPool` to make sure that we join all things
 //! properly.
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
 use crossbeam_channel::Sender;
+use stdx::process::ChildRegistry;
+
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+const DETACHED_THREADS: usize = 2;
 
 pub(crate) struct TaskPool<T> {
     sender: Sender<T>,
     inner: threadpool::ThreadPool,
+    /// Runs the long running jobs, which are not joined on drop.
+    detached: threadpool::ThreadPool,
+    /// The names of the detached jobs which haven't finished yet.
+    detached_running: Arc<Mutex<Vec<String>>>,
+    /// The child processes of the detached jobs.
+    children: ChildRegistry,
 }
 
 impl<T> TaskPool<T> {
     pub(crate) fn new_with_threads(sender: Sender<T>, threads: usize) -> TaskPool<T> {
-        const STACK_SIZE: usize = 8 * 1024 * 1024;
-
         let inner = threadpool::Builder::new()
             .thread_name("Worker".into())
             .thread_stack_size(STACK_SIZE)
             .num_threads(threads)
             .build();
-        TaskPool { sender, inner }
+        let detached = threadpool::Builder::new()
+            .thread_name("DetachedWorker".into())
+            .thread_stack_size(STACK_SIZE)
+            .num_threads(DETACHED_THREADS)
+            .build();
+        TaskPool {
+            sender,
+            inner,
+            detached,
+            detached_running: Default::default(),
+            children: Default::default(),
+        }
     }
 
     pub(crate) fn spawn<F>(&mut self, task: F)
@@ -41,6 +66,58 @@ impl<T> TaskPool<T> {
         })
     }
 
+    /// Like `spawn_with_sender`, but runs the task on a separate pool which is
+    /// not joined when this one is dropped. This is used for long running jobs
+    /// like `cargo metadata` which shouldn't keep the server from exiting, see
+    /// [`TaskPool::shutdown`].
+    pub(crate) fn spawn_detached_with_sender<F>(&mut self, name: &str, task: F)
+    where
+        F: FnOnce(Sender<T>) + Send + 'static,
+        T: Send + 'static,
+    {
+        self.detached_running.lock().unwrap().push(name.to_owned());
+        self.detached.execute({
+            let sender = self.sender.clone();
+            let name = name.to_owned();
+            let running = self.detached_running.clone();
+            let children = self.children.clone();
+            move || {
+                let _finished = stdx::defer(|| {
+                    let mut running = running.lock().unwrap();
+                    if let Some(idx) = running.iter().position(|it| *it == name) {
+                        running.swap_remove(idx);
+                    }
+                });
+                children.scope(|| task(sender))
+            }
+        })
+    }
+
+    /// Waits up to `timeout` for the detached tasks to finish. If some of them
+    /// don't, their child processes are killed and their names are returned.
+    pub(crate) fn shutdown(&mut self, timeout: Duration) -> Result<(), Vec<String>> {
+        let (finished_sender, finished) = crossbeam_channel::bounded(1);
+        let detached = self.detached.clone();
+        thread::Builder::new()
+            .name("DetachedJoin".to_owned())
+            .spawn(move || {
+                detached.join();
+                let _ = finished_sender.send(());
+            })
+            .expect("failed to spawn thread");
+        if finished.recv_timeout(timeout).is_ok() {
+            return Ok(());
+        }
+        self.children.kill_all();
+        let mut running = self.detached_running.lock().unwrap().clone();
+        running.sort();
+        if running.is_empty() {
+            // They finished in the meantime.
+            return Ok(());
+        }
+        Err(running)
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.inner.queued_count()
     }
@@ -51,3 +128,49 @@ impl<T> Drop for TaskPool<T> {
         self.inner.join()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn drop_does_not_join_detached_tasks() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (_unblock, blocked) = crossbeam_channel::bounded::<()>(0);
+        let mut pool = TaskPool::new_with_threads(sender, 1);
+        pool.spawn_detached_with_sender("Detached", move |sender| {
+            let _ = blocked.recv();
+            let _ = sender.send("detached");
+        });
+        pool.spawn(|| "pooled");
+        drop(pool);
+        assert_eq!(receiver.recv(), Ok("pooled"));
+    }
+
+    #[test]
+    fn shutdown_kills_children_of_unfinished_tasks() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut pool = TaskPool::new_with_threads(sender, 1);
+        pool.spawn_detached_with_sender("Sleep", |sender| {
+            let cmd = if cfg!(windows) {
+                let mut cmd = Command::new("ping");
+                cmd.args(["-n", "60", "127.0.0.1"]);
+                cmd
+            } else {
+                let mut cmd = Command::new("sleep");
+                cmd.arg("60");
+                cmd
+            };
+            let output = stdx::process::spawn_with_streaming_output(cmd, &mut |_| (), &mut |_| ());
+            let _ = sender.send(output.map_or(false, |it| it.status.success()));
+        });
+        pool.spawn_detached_with_sender("Quick", |_| ());
+
+        assert_eq!(pool.shutdown(Duration::from_millis(200)), Err(vec!["Sleep".to_owned()]));
+        // The killed child unblocks the task long before it would have finished.
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(30)), Ok(false));
+        assert_eq!(pool.shutdown(Duration::from_secs(30)), Ok(()));
+    }
+}
@@ -0,0 +1,82 @@
+//! Aggregated wall-clock timings of requests and background jobs, reported
+//! by `rust-analyzer/analyzerStatus`.
+
+use std::{fmt, time::Duration};
+
+use rustc_hash::FxHashMap;
+
+/// Upper bounds of the histogram buckets, the last bucket is unbounded.
+const BUCKETS: [Duration; 4] = [
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+];
+
+#[derive(Default, Debug)]
+pub(crate) struct Timings {
+    entries: FxHashMap<String, Entry>,
+}
+
+#[derive(Default, Debug)]
+struct Entry {
+    count: u64,
+    total: Duration,
+    max: Duration,
+    histogram: [u64; BUCKETS.len() + 1],
+}
+
+impl Timings {
+    pub(crate) fn record(&mut self, name: &str, duration: Duration) {
+        let entry = match self.entries.get_mut(name) {
+            Some(it) => it,
+            None => self.entries.entry(name.to_owned()).or_default(),
+        };
+        entry.count += 1;
+        entry.total += duration;
+        entry.max = entry.max.max(duration);
+        let bucket = BUCKETS.iter().position(|&bound| duration < bound).unwrap_or(BUCKETS.len());
+        entry.histogram[bucket] += 1;
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|(_, a), (_, b)| b.total.cmp(&a.total));
+        for (name, entry) in entries {
+            let mean = entry.total / entry.count as u32;
+            writeln!(
+                f,
+                "{name}: {} calls, mean {mean:0.2?}, max {:0.2?}, total {:0.2?}",
+                entry.count, entry.max, entry.total,
+            )?;
+            let [a, b, c, d, e] = entry.histogram;
+            writeln!(f, "    <10ms: {a}, <100ms: {b}, <1s: {c}, <10s: {d}, >=10s: {e}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_by_name() {
+        let mut timings = Timings::default();
+        timings.record("textDocument/hover", Duration::from_millis(5));
+        timings.record("textDocument/hover", Duration::from_millis(15));
+        timings.record("fetch workspaces", Duration::from_secs(12));
+
+        assert_eq!(
+            timings.to_string(),
+            "\
+fetch workspaces: 1 calls, mean 12.00s, max 12.00s, total 12.00s
+    <10ms: 0, <100ms: 0, <1s: 0, <10s: 0, >=10s: 1
+textDocument/hover: 2 calls, mean 10.00ms, max 15.00ms, total 20.00ms
+    <10ms: 1, <100ms: 1, <1s: 0, <10s: 0, >=10s: 0
+"
+        );
+    }
+}
@@ -15,6 +15,7 @@ use ide::{
 };
 use itertools::Itertools;
 use serde_json::to_value;
+use stdx::hash::NoHashHashMap;
 use vfs::AbsPath;
 
 use crate::{
@@ -667,9 +668,11 @@ fn semantic_token_type_and_modifiers(
         HlTag::BuiltinType => semantic_tokens::BUILTIN_TYPE,
         HlTag::ByteLiteral | HlTag::NumericLiteral => semantic_tokens::NUMBER,
         HlTag::CharLiteral => semantic_tokens::CHAR,
+        HlTag::CodeSpan => semantic_tokens::CODE_SPAN,
         HlTag::Comment => semantic_tokens::COMMENT,
         HlTag::EscapeSequence => semantic_tokens::ESCAPE_SEQUENCE,
         HlTag::FormatSpecifier => semantic_tokens::FORMAT_SPECIFIER,
+        HlTag::InvalidEscapeSequence => semantic_tokens::INVALID_ESCAPE_SEQUENCE,
         HlTag::Keyword => semantic_tokens::KEYWORD,
         HlTag::None => semantic_tokens::GENERIC,
         HlTag::Operator(op) => match op {
@@ -680,6 +683,7 @@ fn semantic_token_type_and_modifiers(
             HlOperator::Other => semantic_tokens::OPERATOR,
         },
         HlTag::StringLiteral => semantic_tokens::STRING,
+        HlTag::TaskMarker => semantic_tokens::TASK_MARKER,
         HlTag::UnresolvedReference => semantic_tokens::UNRESOLVED_REFERENCE,
         HlTag::Punctuation(punct) => match punct {
             HlPunct::Bracket => semantic_tokens::BRACKET,
@@ -991,14 +995,88 @@ pub(crate) fn snippet_text_document_ops(
     Ok(ops)
 }
 
+/// Turns a move into a create of each moved file with its (edited) contents,
+/// followed by a delete of the original, for clients which can't rename files.
+/// The edits of the moved files are taken out of `edits`, as the originals are
+/// gone by the time they would be applied.
+fn emulated_move_ops(
+    snap: &GlobalStateSnapshot,
+    file_system_edit: &FileSystemEdit,
+    edits: &mut NoHashHashMap<FileId, TextEdit>,
+) -> Cancellable<Option<Vec<lsp_ext::SnippetDocumentChangeOperation>>> {
+    let moved_files = match file_system_edit {
+        FileSystemEdit::CreateFile { .. } => return Ok(None),
+        FileSystemEdit::MoveFile { src, dst } => vec![(*src, snap.anchored_path(dst))],
+        FileSystemEdit::MoveDir { src, src_id: _, dst } => {
+            let Some(base) = snap.file_id_to_file_path(src.anchor).parent() else {
+                return Ok(None);
+            };
+            let (src_dir, dst_dir) = (base.join(&src.path), base.join(&dst.path));
+            let (Some(src_dir), Some(dst_dir)) = (src_dir, dst_dir) else { return Ok(None) };
+            snap.files_in_dir(&src_dir)
+                .into_iter()
+                .filter_map(|(file_id, rel_path)| {
+                    let dst = dst_dir.join(&rel_path)?;
+                    Some((file_id, url_from_abs_path(dst.as_path()?)))
+                })
+                .collect()
+        }
+    };
+
+    let mut ops = Vec::new();
+    for (file_id, uri) in moved_files {
+        let mut text = snap.analysis.file_text(file_id)?.to_string();
+        if let Some(edit) = edits.remove(&file_id) {
+            edit.apply(&mut text);
+        }
+        if snap.file_line_index(file_id)?.endings == LineEndings::Dos {
+            text = text.replace('\n', "\r\n");
+        }
+        let create_file = lsp_types::ResourceOp::Create(lsp_types::CreateFile {
+            uri: uri.clone(),
+            options: None,
+            annotation_id: None,
+        });
+        ops.push(lsp_ext::SnippetDocumentChangeOperation::Op(create_file));
+        if !text.is_empty() {
+            let text_document =
+                lsp_types::OptionalVersionedTextDocumentIdentifier { uri, version: None };
+            let text_edit = lsp_ext::SnippetTextEdit {
+                range: lsp_types::Range::default(),
+                new_text: text,
+                insert_text_format: Some(lsp_types::InsertTextFormat::PLAIN_TEXT),
+                annotation_id: None,
+            };
+            let edit_file =
+                lsp_ext::SnippetTextDocumentEdit { text_document, edits: vec![text_edit] };
+            ops.push(lsp_ext::SnippetDocumentChangeOperation::Edit(edit_file));
+        }
+        let delete_file = lsp_types::ResourceOp::Delete(lsp_types::DeleteFile {
+            uri: snap.file_id_to_url(file_id),
+            options: None,
+        });
+        ops.push(lsp_ext::SnippetDocumentChangeOperation::Op(delete_file));
+    }
+    Ok(Some(ops))
+}
+
 pub(crate) fn snippet_workspace_edit(
     snap: &GlobalStateSnapshot,
-    source_change: SourceChange,
+    mut source_change: SourceChange,
 ) -> Cancellable<lsp_ext::SnippetWorkspaceEdit> {
     let mut document_changes: Vec<lsp_ext::SnippetDocumentChangeOperation> = Vec::new();
 
+    let emulate_renames = snap.config.emulate_file_renames();
     for op in source_change.file_system_edits {
-        let ops = snippet_text_document_ops(snap, op)?;
+        let emulated = if emulate_renames {
+            emulated_move_ops(snap, &op, &mut source_change.source_file_edits)?
+        } else {
+            None
+        };
+        let ops = match emulated {
+            Some(it) => it,
+            None => snippet_text_document_ops(snap, op)?,
+        };
         document_changes.extend_from_slice(&ops);
     }
     for (file_id, edit) in source_change.source_file_edits {
@@ -1448,6 +1526,54 @@ fn main() {
         }
     }
 
+    #[test]
+    fn text_edits_keep_crlf_line_endings() {
+        // Applies the converted edits to the original text, like a client would.
+        fn check(before: &str, edit: impl FnOnce(&str) -> TextEdit, after: &str) {
+            let (text, endings) = LineEndings::normalize(before.to_owned());
+            let line_index = LineIndex {
+                index: Arc::new(ide::LineIndex::new(&text)),
+                endings,
+                encoding: PositionEncoding::Utf8,
+            };
+            let changes = text_edit_vec(&line_index, edit(&text))
+                .into_iter()
+                .rev()
+                .map(|it| lsp_types::TextDocumentContentChangeEvent {
+                    range: Some(it.range),
+                    range_length: None,
+                    text: it.new_text,
+                })
+                .collect();
+            let actual = crate::lsp_utils::apply_document_changes(
+                PositionEncoding::Utf8,
+                || before.to_owned(),
+                changes,
+            );
+            assert_eq!(actual, after);
+        }
+
+        let edit = |text: &str| {
+            let offset = |needle: &str| TextSize::try_from(text.find(needle).unwrap()).unwrap();
+            let mut builder = TextEdit::builder();
+            builder.replace(TextRange::at(offset("foo"), TextSize::of("foo")), "baz".to_owned());
+            builder.insert(offset("bar();") + TextSize::of("bar();"), "\n    qux();".to_owned());
+            builder.delete(TextRange::at(offset("quux"), TextSize::of("quux();\n    ")));
+            builder.finish()
+        };
+
+        check(
+            "fn main() {\r\n    foo();\r\n    bar();\r\n    quux();\r\n    end();\r\n}\r\n",
+            edit,
+            "fn main() {\r\n    baz();\r\n    bar();\r\n    qux();\r\n    end();\r\n}\r\n",
+        );
+        check(
+            "fn main() {\n    foo();\r\n    bar();\n    quux();\r\n    end();\n}\r\n",
+            edit,
+            "fn main() {\n    baz();\r\n    bar();\r\n    qux();\n    end();\n}\r\n",
+        );
+    }
+
     // `Url` is not able to parse windows paths on unix machines.
     #[test]
     #[cfg(target_os = "windows")]
@@ -1460,4 +1586,76 @@ fn main() {
         let url = url_from_abs_path(Path::new(r#"\\localhost\C$\my_dir"#).try_into().unwrap());
         assert_eq!(url.to_string(), "file://localhost/C$/my_dir");
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn url_round_trip_unusual_paths() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::Path};
+
+        use vfs::AbsPathBuf;
+
+        let check = |path: &Path, expect: &str| {
+            let path = AbsPathBuf::try_from(path.to_path_buf()).unwrap();
+            let url = url_from_abs_path(&path);
+            assert_eq!(url.as_str(), expect);
+            assert_eq!(crate::from_proto::abs_path(&url).unwrap(), path);
+        };
+
+        check(Path::new("/my dir/lib.rs"), "file:///my%20dir/lib.rs");
+        check(Path::new("/a#b/c?d.rs"), "file:///a%23b/c%3Fd.rs");
+        check(Path::new("/100%/lib.rs"), "file:///100%25/lib.rs");
+        check(Path::new("/крейт/ü.rs"), "file:///%D0%BA%D1%80%D0%B5%D0%B9%D1%82/%C3%BC.rs");
+        check(Path::new(OsStr::from_bytes(b"/vendor/\xFF.rs")), "file:///vendor/%FF.rs");
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn url_round_trip_unusual_paths() {
+        use std::path::Path;
+
+        use vfs::AbsPathBuf;
+
+        let check = |path: &str, expect: &str| {
+            let path = AbsPathBuf::try_from(Path::new(path).to_path_buf()).unwrap();
+            let url = url_from_abs_path(&path);
+            assert_eq!(url.as_str(), expect);
+            assert_eq!(crate::from_proto::abs_path(&url).unwrap(), path);
+        };
+
+        check(r"c:\my dir\lib.rs", "file:///c:/my%20dir/lib.rs");
+        check(r"c:\a#b\c.rs", "file:///c:/a%23b/c.rs");
+        check(r"c:\крейт\ü.rs", "file:///c:/%D0%BA%D1%80%D0%B5%D0%B9%D1%82/%C3%BC.rs");
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn windows_path_spellings_agree() {
+        use std::path::Path;
+
+        use vfs::{AbsPathBuf, VfsPath};
+
+        let abs = |path: &str| AbsPathBuf::try_from(Path::new(path).to_path_buf()).unwrap();
+        let from_url =
+            |url: &str| crate::from_proto::abs_path(&lsp_types::Url::parse(url).unwrap()).unwrap();
+
+        let expected = VfsPath::from(abs(r"C:\work\proj\src\lib.rs"));
+        for spelling in [r"c:\work\proj\src\lib.rs", r"\\?\C:\work\proj\src\lib.rs"] {
+            assert_eq!(VfsPath::from(abs(spelling)), expected);
+        }
+        for url in ["file:///c%3A/work/proj/src/lib.rs", "file:///C:/work/proj/src/lib.rs"] {
+            assert_eq!(VfsPath::from(from_url(url)), expected);
+        }
+        assert_eq!(
+            url_from_abs_path(&abs(r"\\?\C:\work\proj\src\lib.rs")).as_str(),
+            "file:///c:/work/proj/src/lib.rs"
+        );
+
+        let expected = VfsPath::from(abs(r"\\server\share\proj\lib.rs"));
+        assert_eq!(VfsPath::from(abs(r"\\?\UNC\server\share\proj\lib.rs")), expected);
+        assert_eq!(VfsPath::from(from_url("file://server/share/proj/lib.rs")), expected);
+        assert_eq!(
+            url_from_abs_path(&abs(r"\\?\UNC\server\share\proj\lib.rs")).as_str(),
+            "file://server/share/proj/lib.rs"
+        );
+    }
 }
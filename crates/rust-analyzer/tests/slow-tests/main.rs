@@ -18,23 +18,25 @@ mod tidy;
 
 use std::{collections::HashMap, path::PathBuf, time::Instant};
 
+use lsp_server::ErrorCode;
 use lsp_types::{
-    notification::DidOpenTextDocument,
+    notification::{DidOpenTextDocument, DidSaveTextDocument, Exit},
     request::{
-        CodeActionRequest, Completion, Formatting, GotoTypeDefinition, HoverRequest,
-        WillRenameFiles, WorkspaceSymbolRequest,
+        CodeActionRequest, Completion, Formatting, GotoTypeDefinition, HoverRequest, Rename,
+        Shutdown, WillRenameFiles, WorkspaceSymbolRequest,
     },
     CodeActionContext, CodeActionParams, CompletionParams, DidOpenTextDocumentParams,
-    DocumentFormattingParams, FileRename, FormattingOptions, GotoDefinitionParams, HoverParams,
-    PartialResultParams, Position, Range, RenameFilesParams, TextDocumentItem,
-    TextDocumentPositionParams, WorkDoneProgressParams,
+    DidSaveTextDocumentParams, DocumentFormattingParams, FileRename, FormattingOptions,
+    GotoDefinitionParams, HoverParams, PartialResultParams, Position, Range, RenameFilesParams,
+    RenameParams, ResourceOperationKind, TextDocumentItem, TextDocumentPositionParams,
+    WorkDoneProgressParams,
 };
 use rust_analyzer::lsp_ext::{OnEnter, Runnables, RunnablesParams};
 use serde_json::json;
 use test_utils::skip_slow_tests;
 
 use crate::{
-    support::{project, Project},
+    support::{project, Project, StdioServer},
     testdir::TestDir,
 };
 
@@ -1097,3 +1099,218 @@ pub fn bar() {}
 
     server.request::<WorkspaceSymbolRequest>(Default::default(), json!([]));
 }
+
+#[test]
+fn save_checks_only_the_saved_package() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[workspace]
+members = ["a", "b"]
+
+//- /a/Cargo.toml
+[package]
+name = "a"
+version = "0.0.0"
+
+//- /a/src/lib.rs
+pub fn a() {}
+
+//- /b/Cargo.toml
+[package]
+name = "b"
+version = "0.0.0"
+
+//- /b/src/lib.rs
+pub fn b() {}
+"#,
+    )
+    .with_config(json!({
+        "check": {
+            "workspace": false
+        }
+    }))
+    .with_work_done_progress()
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    // Wait for the initial check, and touch both packages so that the next
+    // check doesn't see them as fresh.
+    server.wait_for_flycheck();
+    std::fs::write(server.path().join("a/src/lib.rs"), "pub fn a2() {}").unwrap();
+    std::fs::write(server.path().join("b/src/lib.rs"), "pub fn b2() {}").unwrap();
+
+    server.notification::<DidSaveTextDocument>(DidSaveTextDocumentParams {
+        text_document: server.doc_id("a/src/lib.rs"),
+        text: None,
+    });
+    let checked = server.wait_for_flycheck();
+    assert!(checked.iter().any(|it| it == "a"), "{checked:?}");
+    assert!(!checked.iter().any(|it| it == "b"), "{checked:?}");
+}
+
+#[test]
+fn exits_cleanly_after_shutdown() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let mut server = StdioServer::spawn();
+    let res = server.request::<Shutdown>(());
+    assert!(res.error.is_none(), "{res:?}");
+    let res = server.request::<WorkspaceSymbolRequest>(Default::default());
+    assert_eq!(res.error.map(|it| it.code), Some(ErrorCode::InvalidRequest as i32));
+    server.notification::<Exit>(());
+    assert_eq!(server.wait(), Some(0));
+}
+
+#[test]
+fn exit_without_shutdown_is_an_error() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let mut server = StdioServer::spawn();
+    server.notification::<Exit>(());
+    assert_eq!(server.wait(), Some(1));
+}
+
+#[test]
+fn closing_stdin_without_exit_is_an_error() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let mut server = StdioServer::spawn();
+    server.close_stdin();
+    assert_eq!(server.wait(), Some(1));
+}
+
+#[test]
+fn rename_module_without_rename_file_support() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+mod foo;
+mod baz;
+
+//- /src/foo.rs
+pub fn f() {}
+
+//- /src/baz/mod.rs
+mod inner;
+
+//- /src/baz/inner.rs
+use crate::foo::f;
+"#,
+    )
+    .with_resource_operations(vec![ResourceOperationKind::Create, ResourceOperationKind::Delete])
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let rename = |line, character, new_name: &str| RenameParams {
+        text_document_position: TextDocumentPositionParams::new(
+            server.doc_id("src/lib.rs"),
+            Position::new(line, character),
+        ),
+        new_name: new_name.to_owned(),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+    };
+
+    // The moved file is recreated under its new name.
+    server.request::<Rename>(
+        rename(0, 4, "bar"),
+        json!({
+            "documentChanges": [
+                { "kind": "create", "uri": "file://[..]/src/bar.rs" },
+                {
+                    "textDocument": { "uri": "file://[..]/src/bar.rs", "version": null },
+                    "edits": [{
+                        "newText": "pub fn f() {}\n\n",
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 0 }
+                        }
+                    }]
+                },
+                { "kind": "delete", "uri": "file://[..]/src/foo.rs" },
+                {
+                    "textDocument": { "uri": "file://[..]/src/lib.rs", "version": null },
+                    "edits": [{
+                        "newText": "bar",
+                        "range": {
+                            "start": { "line": 0, "character": 4 },
+                            "end": { "line": 0, "character": 7 }
+                        }
+                    }]
+                },
+                {
+                    "textDocument": { "uri": "file://[..]/src/baz/inner.rs", "version": null },
+                    "edits": [{
+                        "newText": "bar",
+                        "range": {
+                            "start": { "line": 0, "character": 11 },
+                            "end": { "line": 0, "character": 14 }
+                        }
+                    }]
+                }
+            ]
+        }),
+    );
+
+    // So are all the files of a `mod.rs` module, with their edits applied.
+    server.request::<Rename>(
+        rename(1, 4, "qux"),
+        json!({
+            "documentChanges": [
+                { "kind": "create", "uri": "file://[..]/src/qux/mod.rs" },
+                {
+                    "textDocument": { "uri": "file://[..]/src/qux/mod.rs", "version": null },
+                    "edits": [{
+                        "newText": "mod inner;\n\n",
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 0 }
+                        }
+                    }]
+                },
+                { "kind": "delete", "uri": "file://[..]/src/baz/mod.rs" },
+                { "kind": "create", "uri": "file://[..]/src/qux/inner.rs" },
+                {
+                    "textDocument": { "uri": "file://[..]/src/qux/inner.rs", "version": null },
+                    "edits": [{
+                        "newText": "use crate::foo::f;\n",
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 0 }
+                        }
+                    }]
+                },
+                { "kind": "delete", "uri": "file://[..]/src/baz/inner.rs" },
+                {
+                    "textDocument": { "uri": "file://[..]/src/lib.rs", "version": null },
+                    "edits": [{
+                        "newText": "qux",
+                        "range": {
+                            "start": { "line": 1, "character": 4 },
+                            "end": { "line": 1, "character": 7 }
+                        }
+                    }]
+                }
+            ]
+        }),
+    );
+}
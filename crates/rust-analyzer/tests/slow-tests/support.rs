@@ -1,14 +1,22 @@
 use std::{
     cell::{Cell, RefCell},
     fs,
+    io::{self, BufReader},
     path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
     sync::Once,
-    time::Duration,
+    thread,
+    time::{Duration, Instant},
 };
 
 use crossbeam_channel::{after, select, Receiver};
-use lsp_server::{Connection, Message, Notification, Request};
-use lsp_types::{notification::Exit, request::Shutdown, TextDocumentIdentifier, Url};
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{Exit, Initialized},
+    request::{Initialize, Request as _, Shutdown},
+    InitializedParams, ProgressParams, ProgressParamsValue, ProgressToken, TextDocumentIdentifier,
+    Url, WorkDoneProgress,
+};
 use project_model::ProjectManifest;
 use rust_analyzer::{config::Config, lsp_ext, main_loop};
 use serde::Serialize;
@@ -23,6 +31,8 @@ pub(crate) struct Project<'a> {
     tmp_dir: Option<TestDir>,
     roots: Vec<PathBuf>,
     config: serde_json::Value,
+    work_done_progress: bool,
+    resource_operations: Option<Vec<lsp_types::ResourceOperationKind>>,
 }
 
 impl<'a> Project<'a> {
@@ -31,6 +41,8 @@ impl<'a> Project<'a> {
             fixture,
             tmp_dir: None,
             roots: vec![],
+            work_done_progress: false,
+            resource_operations: None,
             config: serde_json::json!({
                 "cargo": {
                     // Loading standard library is costly, let's ignore it by default
@@ -54,6 +66,19 @@ impl<'a> Project<'a> {
         self
     }
 
+    pub(crate) fn with_work_done_progress(mut self) -> Project<'a> {
+        self.work_done_progress = true;
+        self
+    }
+
+    pub(crate) fn with_resource_operations(
+        mut self,
+        kinds: Vec<lsp_types::ResourceOperationKind>,
+    ) -> Project<'a> {
+        self.resource_operations = Some(kinds);
+        self
+    }
+
     pub(crate) fn with_config(mut self, config: serde_json::Value) -> Project<'a> {
         fn merge(dst: &mut serde_json::Value, src: serde_json::Value) {
             match (dst, src) {
@@ -110,6 +135,13 @@ impl<'a> Project<'a> {
                             relative_pattern_support: None,
                         },
                     ),
+                    workspace_edit: self.resource_operations.map(|kinds| {
+                        lsp_types::WorkspaceEditClientCapabilities {
+                            document_changes: Some(true),
+                            resource_operations: Some(kinds),
+                            ..Default::default()
+                        }
+                    }),
                     ..Default::default()
                 }),
                 text_document: Some(lsp_types::TextDocumentClientCapabilities {
@@ -130,7 +162,7 @@ impl<'a> Project<'a> {
                     ..Default::default()
                 }),
                 window: Some(lsp_types::WindowClientCapabilities {
-                    work_done_progress: Some(false),
+                    work_done_progress: Some(self.work_done_progress),
                     ..Default::default()
                 }),
                 experimental: Some(json!({
@@ -230,6 +262,9 @@ impl Server {
                             continue;
                         }
                     }
+                    if req.method == "window/workDoneProgress/create" {
+                        continue;
+                    }
                     panic!("unexpected request: {req:?}")
                 }
                 Message::Notification(_) => (),
@@ -258,6 +293,39 @@ impl Server {
         .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
         self
     }
+    /// Waits for the next run of the first flycheck to finish and returns
+    /// the targets it reported as checked. Needs
+    /// [`Project::with_work_done_progress`].
+    pub(crate) fn wait_for_flycheck(&self) -> Vec<String> {
+        let progress = |msg: &Message| match msg {
+            Message::Notification(n) if n.method == "$/progress" => {
+                let params = n.clone().extract::<ProgressParams>("$/progress").unwrap();
+                match (params.token, params.value) {
+                    (ProgressToken::String(token), ProgressParamsValue::WorkDone(progress))
+                        if token == "rust-analyzer/flycheck/0" =>
+                    {
+                        Some(progress)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let is_end = |msg: &Message| matches!(progress(msg), Some(WorkDoneProgress::End(_)));
+        let finished = self.messages.borrow().iter().filter(|msg| is_end(msg)).count();
+        self.wait_for_message_cond(finished + 1, &is_end)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for flycheck"));
+        let messages = self.messages.borrow();
+        let mut runs = messages.split(is_end);
+        runs.nth(finished)
+            .unwrap()
+            .iter()
+            .filter_map(|msg| match progress(msg)? {
+                WorkDoneProgress::Report(report) => report.message,
+                _ => None,
+            })
+            .collect()
+    }
     fn wait_for_message_cond(
         &self,
         n: usize,
@@ -301,6 +369,96 @@ impl Drop for Server {
     }
 }
 
+/// The real `rust-analyzer` binary, talking LSP over its stdio, for the tests
+/// which care about how the process ends.
+pub(crate) struct StdioServer {
+    req_id: i32,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    dir: TestDir,
+}
+
+impl StdioServer {
+    pub(crate) fn spawn() -> StdioServer {
+        let dir = TestDir::new();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust-analyzer"))
+            .current_dir(dir.path())
+            .env_remove("RA_LOG")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to spawn rust-analyzer");
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut server = StdioServer { req_id: 1, child, stdin, stdout, dir };
+
+        let root_uri = Url::from_file_path(server.dir.path()).unwrap();
+        let res = server.send_request(Request::new(
+            0.into(),
+            Initialize::METHOD.to_owned(),
+            json!({ "processId": null, "rootUri": root_uri, "capabilities": {} }),
+        ));
+        assert!(res.error.is_none(), "{res:?}");
+        server.notification::<Initialized>(InitializedParams {});
+        server
+    }
+
+    pub(crate) fn notification<N>(&mut self, params: N::Params)
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: Serialize,
+    {
+        self.send(Notification::new(N::METHOD.to_owned(), params).into());
+    }
+
+    pub(crate) fn request<R>(&mut self, params: R::Params) -> Response
+    where
+        R: lsp_types::request::Request,
+        R::Params: Serialize,
+    {
+        let id = self.req_id;
+        self.req_id += 1;
+        self.send_request(Request::new(id.into(), R::METHOD.to_owned(), params))
+    }
+
+    fn send_request(&mut self, req: Request) -> Response {
+        let id = req.id.clone();
+        self.send(req.into());
+        loop {
+            match Message::read(&mut self.stdout).unwrap() {
+                Some(Message::Response(res)) if res.id == id => return res,
+                Some(_) => (),
+                None => panic!("the server exited before responding to {id:?}"),
+            }
+        }
+    }
+
+    fn send(&mut self, msg: Message) {
+        msg.write(self.stdin.as_mut().expect("stdin is closed")).unwrap();
+    }
+
+    pub(crate) fn close_stdin(&mut self) {
+        self.stdin = None;
+    }
+
+    /// Waits for the server to exit on its own, and returns its exit code.
+    pub(crate) fn wait(mut self) -> Option<i32> {
+        let mut stdout = self.stdout;
+        let _drain = jod_thread::spawn(move || io::copy(&mut stdout, &mut io::sink()));
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while Instant::now() < deadline {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status.code();
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        self.child.kill().unwrap();
+        panic!("the server didn't exit");
+    }
+}
+
 struct Timeout;
 
 fn recv_timeout(receiver: &Receiver<Message>) -> Result<Option<Message>, Timeout> {
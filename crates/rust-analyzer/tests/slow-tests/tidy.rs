@@ -152,6 +152,7 @@ fn check_licenses() {
 (MIT OR Apache-2.0) AND Unicode-DFS-2016
 0BSD OR MIT OR Apache-2.0
 Apache-2.0
+Apache-2.0 / MIT
 Apache-2.0 OR BSL-1.0
 Apache-2.0 OR MIT
 Apache-2.0 WITH LLVM-exception OR Apache-2.0 OR MIT
@@ -223,6 +224,8 @@ fn check_todo(path: &Path, text: &str) {
         "ide-assists/src/tests/generated.rs",
         // The tests for missing fields
         "ide-diagnostics/src/handlers/missing_fields.rs",
+        // Task markers are highlighted in comments.
+        "syntax_highlighting/comment.rs",
     ];
     if need_todo.iter().any(|p| path.ends_with(p)) {
         return;
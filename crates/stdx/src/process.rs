@@ -4,8 +4,10 @@
 //! <https://github.com/rust-lang/cargo/blob/58a961314437258065e23cb6316dfc121d96fb71/crates/cargo-util/src/process_builder.rs#L231>
 
 use std::{
+    cell::RefCell,
     io,
     process::{ChildStderr, ChildStdout, Command, Output, Stdio},
+    sync::{Arc, Mutex},
 };
 
 use crate::JodChild;
@@ -58,16 +60,85 @@ pub fn spawn_with_streaming_output(
     let cmd = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).stdin(Stdio::null());
 
     let mut child = JodChild(cmd.spawn()?);
-    let (stdout, stderr) = streaming_output(
-        child.stdout.take().unwrap(),
-        child.stderr.take().unwrap(),
-        on_stdout_line,
-        on_stderr_line,
-    )?;
+    let (out, err) = (child.stdout.take().unwrap(), child.stderr.take().unwrap());
+    let child = ChildRegistry::register(child);
+    let output = streaming_output(out, err, on_stdout_line, on_stderr_line);
+    let mut child = child.into_inner();
+    let (stdout, stderr) = output?;
     let status = child.wait()?;
     Ok(Output { status, stdout, stderr })
 }
 
+thread_local! {
+    static CURRENT_REGISTRY: RefCell<Option<ChildRegistry>> = RefCell::new(None);
+}
+
+/// Keeps track of the children spawned by [`spawn_with_streaming_output`] on
+/// the threads running in [`ChildRegistry::scope`], so that another thread
+/// can kill them, e.g. to abandon a hanging `cargo metadata` on exit.
+#[derive(Clone, Debug, Default)]
+pub struct ChildRegistry(Arc<Mutex<RegistryData>>);
+
+#[derive(Debug, Default)]
+struct RegistryData {
+    next_id: u64,
+    children: Vec<(u64, JodChild)>,
+    killed: bool,
+}
+
+impl ChildRegistry {
+    /// Runs `f`, registering the children it spawns in `self`.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let prev = CURRENT_REGISTRY.with(|it| it.replace(Some(self.clone())));
+        let _restore = crate::defer(|| CURRENT_REGISTRY.with(|it| *it.borrow_mut() = prev));
+        f()
+    }
+
+    /// Kills the registered children, as well as those spawned from now on.
+    pub fn kill_all(&self) {
+        let mut data = self.0.lock().unwrap();
+        data.killed = true;
+        for (_, child) in &mut data.children {
+            let _ = child.kill();
+        }
+    }
+
+    fn register(mut child: JodChild) -> Registered {
+        let Some(registry) = CURRENT_REGISTRY.with(|it| it.borrow().clone()) else {
+            return Registered::Unregistered(child);
+        };
+        let mut data = registry.0.lock().unwrap();
+        if data.killed {
+            let _ = child.kill();
+        }
+        let id = data.next_id;
+        data.next_id += 1;
+        data.children.push((id, child));
+        drop(data);
+        Registered::Registered(registry, id)
+    }
+}
+
+enum Registered {
+    Unregistered(JodChild),
+    Registered(ChildRegistry, u64),
+}
+
+impl Registered {
+    /// Takes the child back once its output is read. It is only ever waited
+    /// for by its owner, so the registry never kills an already reaped process.
+    fn into_inner(self) -> JodChild {
+        match self {
+            Registered::Unregistered(child) => child,
+            Registered::Registered(registry, id) => {
+                let mut data = registry.0.lock().unwrap();
+                let idx = data.children.iter().position(|(it, _)| *it == id).unwrap();
+                data.children.swap_remove(idx).1
+            }
+        }
+    }
+}
+
 #[cfg(unix)]
 mod imp {
     use std::{
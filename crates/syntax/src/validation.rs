@@ -11,7 +11,7 @@ use crate::{
     algo,
     ast::{self, HasAttrs, HasVisibility},
     match_ast, AstNode, SyntaxError,
-    SyntaxKind::{CONST, FN, INT_NUMBER, TYPE_ALIAS},
+    SyntaxKind::{BYTE, BYTE_STRING, CHAR, CONST, FN, INT_NUMBER, STRING, TYPE_ALIAS},
     SyntaxNode, SyntaxToken, TextSize, T,
 };
 
@@ -24,7 +24,8 @@ pub(crate) fn validate(root: &SyntaxNode) -> Vec<SyntaxError> {
     for node in root.descendants() {
         match_ast! {
             match node {
-                ast::Literal(it) => validate_literal(it, &mut errors),
+                ast::Literal(it) => validate_literal(it.token(), &mut errors),
+                ast::TokenTree(it) => validate_token_tree_literals(it, &mut errors),
                 ast::Const(it) => validate_const(it, &mut errors),
                 ast::BlockExpr(it) => block::validate_block_expr(it, &mut errors),
                 ast::FieldExpr(it) => validate_numeric_name(it.name_ref(), &mut errors),
@@ -111,13 +112,22 @@ fn rustc_unescape_error_to_string(err: unescape::EscapeError) -> &'static str {
     err_message
 }
 
-fn validate_literal(literal: ast::Literal, acc: &mut Vec<SyntaxError>) {
+/// The literals in macro calls and definitions are plain tokens instead of
+/// `Literal` nodes, but malformed escapes in them are errors all the same.
+fn validate_token_tree_literals(tt: ast::TokenTree, acc: &mut Vec<SyntaxError>) {
+    for token in tt.syntax().children_with_tokens().filter_map(|it| it.into_token()) {
+        if matches!(token.kind(), STRING | BYTE_STRING | CHAR | BYTE) {
+            validate_literal(token, acc);
+        }
+    }
+}
+
+fn validate_literal(token: SyntaxToken, acc: &mut Vec<SyntaxError>) {
     // FIXME: move this function to outer scope (https://github.com/rust-lang/rust-analyzer/pull/2834#discussion_r366196658)
     fn unquote(text: &str, prefix_len: usize, end_delimiter: char) -> Option<&str> {
         text.rfind(end_delimiter).and_then(|end| text.get(prefix_len..end))
     }
 
-    let token = literal.token();
     let text = token.text();
 
     // FIXME: lift this lambda refactor to `fn` (https://github.com/rust-lang/rust-analyzer/pull/2834#discussion_r366199205)
@@ -126,9 +136,9 @@ fn validate_literal(literal: ast::Literal, acc: &mut Vec<SyntaxError>) {
         acc.push(SyntaxError::new_at_offset(rustc_unescape_error_to_string(err), off));
     };
 
-    match literal.kind() {
-        ast::LiteralKind::String(s) => {
-            if !s.is_raw() {
+    match token.kind() {
+        STRING => {
+            if !text.starts_with('r') {
                 if let Some(without_quotes) = unquote(text, 1, '"') {
                     unescape_literal(without_quotes, Mode::Str, &mut |range, char| {
                         if let Err(err) = char {
@@ -138,8 +148,8 @@ fn validate_literal(literal: ast::Literal, acc: &mut Vec<SyntaxError>) {
                 }
             }
         }
-        ast::LiteralKind::ByteString(s) => {
-            if !s.is_raw() {
+        BYTE_STRING => {
+            if !text.starts_with("br") {
                 if let Some(without_quotes) = unquote(text, 2, '"') {
                     unescape_literal(without_quotes, Mode::ByteStr, &mut |range, char| {
                         if let Err(err) = char {
@@ -149,19 +159,17 @@ fn validate_literal(literal: ast::Literal, acc: &mut Vec<SyntaxError>) {
                 }
             }
         }
-        ast::LiteralKind::Char(_) => {
+        CHAR => {
             if let Some(Err(e)) = unquote(text, 1, '\'').map(unescape_char) {
                 push_err(1, e);
             }
         }
-        ast::LiteralKind::Byte(_) => {
+        BYTE => {
             if let Some(Err(e)) = unquote(text, 2, '\'').map(unescape_byte) {
                 push_err(2, e);
             }
         }
-        ast::LiteralKind::IntNumber(_)
-        | ast::LiteralKind::FloatNumber(_)
-        | ast::LiteralKind::Bool(_) => {}
+        _ => {}
     }
 }
 
@@ -0,0 +1,7 @@
+fn main() {
+    println!("\q {}", '\y');
+    m!(b"\u{1}", r"\q");
+}
+macro_rules! m {
+    () => { "\z" };
+}
@@ -2,12 +2,21 @@
 
 #![warn(rust_2018_idioms, unused_lifetimes, semicolon_in_expressions_from_macros)]
 
-use std::{env, iter, path::PathBuf};
+use std::{collections::HashMap, env, hash::BuildHasher, iter, path::PathBuf};
 
 pub fn cargo() -> PathBuf {
     get_path_for_executable("cargo")
 }
 
+/// Like [`cargo`], but a `CARGO` variable in `extra_env` takes precedence over
+/// the server's own environment.
+pub fn cargo_with_env<S: BuildHasher>(extra_env: &HashMap<String, String, S>) -> PathBuf {
+    match extra_env.get("CARGO") {
+        Some(path) => path.into(),
+        None => cargo(),
+    }
+}
+
 pub fn rustc() -> PathBuf {
     get_path_for_executable("rustc")
 }
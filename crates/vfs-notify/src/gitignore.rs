@@ -0,0 +1,53 @@
+//! Matching of directories against the `.gitignore` files of a source root,
+//! used to skip ignored directories while walking it.
+
+use std::{collections::HashMap, fs};
+
+use ignore::{gitignore::Gitignore, Match};
+use paths::{AbsPath, AbsPathBuf};
+
+/// Parsed `.gitignore` files, keyed by the directory containing them.
+#[derive(Default)]
+pub(crate) struct GitignoreCache {
+    files: HashMap<AbsPathBuf, Option<Gitignore>>,
+}
+
+impl GitignoreCache {
+    /// Returns whether the directory `path` is ignored by a `.gitignore` in
+    /// `root` or in one of the directories between `root` and `path`.
+    pub(crate) fn is_ignored_dir(&mut self, root: &AbsPath, path: &AbsPath) -> bool {
+        if path == root || !path.starts_with(root) {
+            return false;
+        }
+        // Like in git, the rules of a nested `.gitignore` take precedence over
+        // the ones of its parents, so look at the innermost one first.
+        let mut dir = path.parent();
+        while let Some(it) = dir {
+            let gitignore = self.files.entry(it.to_path_buf()).or_insert_with(|| load(it));
+            if let Some(gitignore) = gitignore {
+                match gitignore.matched_path_or_any_parents(path, true) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => (),
+                }
+            }
+            if it == root {
+                break;
+            }
+            dir = it.parent();
+        }
+        false
+    }
+}
+
+fn load(dir: &AbsPath) -> Option<Gitignore> {
+    let path = dir.join(".gitignore");
+    if !fs::metadata(&path).map_or(false, |it| it.is_file()) {
+        return None;
+    }
+    let (gitignore, err) = Gitignore::new(&path);
+    if let Some(err) = err {
+        tracing::warn!("failed to parse {}: {}", path.display(), err);
+    }
+    Some(gitignore)
+}
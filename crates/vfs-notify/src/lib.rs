@@ -9,7 +9,9 @@
 
 #![warn(rust_2018_idioms, unused_lifetimes, semicolon_in_expressions_from_macros)]
 
-use std::fs;
+mod gitignore;
+
+use std::{fs, iter};
 
 use crossbeam_channel::{never, select, unbounded, Receiver, Sender};
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
@@ -17,6 +19,8 @@ use paths::{AbsPath, AbsPathBuf};
 use vfs::loader;
 use walkdir::WalkDir;
 
+use crate::gitignore::GitignoreCache;
+
 #[derive(Debug)]
 pub struct NotifyHandle {
     // Relative order of fields below is significant.
@@ -59,6 +63,7 @@ type NotifyEvent = notify::Result<notify::Event>;
 struct NotifyActor {
     sender: loader::Sender,
     watched_entries: Vec<loader::Entry>,
+    exclude_gitignored: bool,
     // Drop order is significant.
     watcher: Option<(RecommendedWatcher, Receiver<NotifyEvent>)>,
 }
@@ -71,7 +76,12 @@ enum Event {
 
 impl NotifyActor {
     fn new(sender: loader::Sender) -> NotifyActor {
-        NotifyActor { sender, watched_entries: Vec::new(), watcher: None }
+        NotifyActor {
+            sender,
+            watched_entries: Vec::new(),
+            exclude_gitignored: false,
+            watcher: None,
+        }
     }
 
     fn next_event(&self, receiver: &Receiver<Message>) -> Option<Event> {
@@ -106,6 +116,7 @@ impl NotifyActor {
                         self.send(loader::Message::Progress { n_total, n_done: 0, config_version });
 
                         self.watched_entries.clear();
+                        self.exclude_gitignored = config.exclude_gitignored;
 
                         for (i, entry) in config.load.into_iter().enumerate() {
                             let watch = config.watch.contains(&i);
@@ -128,40 +139,58 @@ impl NotifyActor {
                     }
                 },
                 Event::NotifyEvent(event) => {
-                    if let Some(event) = log_notify_error(event) {
-                        let files = event
-                            .paths
-                            .into_iter()
-                            .map(|path| AbsPathBuf::try_from(path).unwrap())
-                            .filter_map(|path| {
-                                let meta = fs::metadata(&path).ok()?;
-                                if meta.file_type().is_dir()
-                                    && self
+                    // Git checkouts and code generators touch many files in a quick
+                    // succession, so coalesce whatever else is queued already before
+                    // reading anything.
+                    let queued = self.watcher.iter().flat_map(|(_, receiver)| receiver.try_iter());
+                    let mut paths = iter::once(event)
+                        .chain(queued)
+                        .filter_map(log_notify_error)
+                        .flat_map(|event| event.paths)
+                        .collect::<Vec<_>>();
+                    paths.sort();
+                    paths.dedup();
+                    let files = paths
+                        .into_iter()
+                        .filter_map(|path| {
+                            let path = AbsPathBuf::try_from(path).ok()?;
+                            let meta = match fs::metadata(&path) {
+                                Ok(it) => it,
+                                // The file is gone, report it as removed.
+                                Err(_) => {
+                                    return self
                                         .watched_entries
                                         .iter()
-                                        .any(|entry| entry.contains_dir(&path))
-                                {
-                                    self.watch(path);
-                                    return None;
-                                }
-
-                                if !meta.file_type().is_file() {
-                                    return None;
+                                        .any(|entry| entry.contains_file(&path))
+                                        .then_some((path, None));
                                 }
-                                if !self
+                            };
+                            if meta.file_type().is_dir() {
+                                if self
                                     .watched_entries
                                     .iter()
-                                    .any(|entry| entry.contains_file(&path))
+                                    .any(|entry| entry.contains_dir(&path))
+                                    && !(self.exclude_gitignored
+                                        && is_gitignored_dir(&self.watched_entries, &path))
                                 {
-                                    return None;
+                                    self.watch(path);
                                 }
+                                return None;
+                            }
 
-                                let contents = read(&path);
-                                Some((path, contents))
-                            })
-                            .collect();
-                        self.send(loader::Message::Loaded { files });
-                    }
+                            if !meta.file_type().is_file() {
+                                return None;
+                            }
+                            if !self.watched_entries.iter().any(|entry| entry.contains_file(&path))
+                            {
+                                return None;
+                            }
+
+                            let contents = read(&path);
+                            Some((path, contents))
+                        })
+                        .collect();
+                    self.send(loader::Message::Loaded { files });
                 }
             }
         }
@@ -185,15 +214,21 @@ impl NotifyActor {
             loader::Entry::Directories(dirs) => {
                 let mut res = Vec::new();
 
+                let exclude_gitignored = self.exclude_gitignored;
+                let mut gitignores = GitignoreCache::default();
                 for root in &dirs.include {
                     let walkdir =
                         WalkDir::new(root).follow_links(true).into_iter().filter_entry(|entry| {
-                            if !entry.file_type().is_dir() {
+                            // Only directories are pruned, files matching the extensions
+                            // are always loaded, even if a `.gitignore` matches them.
+                            let path = AbsPath::assert(entry.path());
+                            if !entry.file_type().is_dir() || root == path {
                                 return true;
                             }
-                            let path = AbsPath::assert(entry.path());
-                            root == path
-                                || dirs.exclude.iter().chain(&dirs.include).all(|it| it != path)
+                            if dirs.exclude.iter().chain(&dirs.include).any(|it| it == path) {
+                                return false;
+                            }
+                            !(exclude_gitignored && gitignores.is_ignored_dir(root, path))
                         });
 
                     let files = walkdir.filter_map(|it| it.ok()).filter_map(|entry| {
@@ -233,6 +268,21 @@ impl NotifyActor {
     }
 }
 
+/// Whether a newly created directory is ignored by a `.gitignore` of the
+/// source root it belongs to.
+fn is_gitignored_dir(entries: &[loader::Entry], path: &AbsPath) -> bool {
+    let mut gitignores = GitignoreCache::default();
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            loader::Entry::Directories(dirs) => Some(&dirs.include),
+            loader::Entry::Files(_) => None,
+        })
+        .flatten()
+        .filter(|root| path.starts_with(root))
+        .any(|root| gitignores.is_ignored_dir(root, path))
+}
+
 fn read(path: &AbsPath) -> Option<Vec<u8>> {
     std::fs::read(path).ok()
 }
@@ -240,3 +290,72 @@ fn read(path: &AbsPath) -> Option<Vec<u8>> {
 fn log_notify_error<T>(res: notify::Result<T>) -> Option<T> {
     res.map_err(|err| tracing::warn!("notify error: {}", err)).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_dir(root: &AbsPath, exclude_gitignored: bool) -> Vec<String> {
+        let mut actor = NotifyActor::new(Box::new(|_| ()));
+        actor.exclude_gitignored = exclude_gitignored;
+        let entry = loader::Entry::rs_files_recursively(root.to_path_buf());
+        let mut files = actor
+            .load_entry(entry, false)
+            .into_iter()
+            .map(|(path, _)| {
+                let path = path.strip_prefix(root).unwrap();
+                path.as_ref().to_str().unwrap().replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    fn fixture(name: &str, files: &[(&str, &str)]) -> AbsPathBuf {
+        let root = std::env::temp_dir().join(format!("vfs-notify-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, text) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        AbsPathBuf::assert(root)
+    }
+
+    #[test]
+    fn skips_gitignored_dirs() {
+        let root = fixture(
+            "skips-gitignored-dirs",
+            &[
+                (".gitignore", "target/\nignored.rs\n"),
+                ("ignored.rs", ""),
+                ("src/lib.rs", ""),
+                ("target/debug/build.rs", ""),
+                ("sub/.gitignore", "gen/\n"),
+                ("sub/gen/mod.rs", ""),
+                ("sub/main.rs", ""),
+            ],
+        );
+        assert_eq!(load_dir(&root, true), ["ignored.rs", "src/lib.rs", "sub/main.rs"]);
+        assert_eq!(
+            load_dir(&root, false),
+            ["ignored.rs", "src/lib.rs", "sub/gen/mod.rs", "sub/main.rs", "target/debug/build.rs"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn loads_reincluded_dirs() {
+        let root = fixture(
+            "loads-reincluded-dirs",
+            &[
+                (".gitignore", "gen*/\n!gen-keep/\n"),
+                ("gen/a.rs", ""),
+                ("gen-keep/b.rs", ""),
+                ("lib.rs", ""),
+            ],
+        );
+        assert_eq!(load_dir(&root, true), ["gen-keep/b.rs", "lib.rs"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt;
 use paths::{AbsPath, AbsPathBuf};
 
 /// A set of files on the file system.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Entry {
     /// The `Entry` is represented by a raw set of files.
     Files(Vec<AbsPathBuf>),
@@ -22,7 +22,7 @@ pub enum Entry {
 /// If many include/exclude paths match, the longest one wins.
 ///
 /// If a path is in both `include` and `exclude`, the `exclude` one wins.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Directories {
     pub extensions: Vec<String>,
     pub include: Vec<AbsPathBuf>,
@@ -41,6 +41,9 @@ pub struct Config {
     ///
     /// If a path in a watched entry is modified,the [`Handle`] should notify it.
     pub watch: Vec<usize>,
+    /// Whether to skip directories ignored by `.gitignore` files when walking
+    /// [`Entry::Directories`].
+    pub exclude_gitignored: bool,
 }
 
 /// Message about an action taken by a [`Handle`].